@@ -1,19 +1,16 @@
-#[cfg(not(feature = "library"))]
-use crate::ContractError;
-use crate::state::{Config, CONFIG, SALE};
+use crate::state::{Config, CONFIG, LEGACY_ENABLED, PENDING_OWNER, sales, sale_type_key, price_key, BUNDLE_COUNT, PendingSwap, PENDING_SWAP, POOLS, MERKLE_ROOT, WHITELIST_MINTED, OFFERS, OFFERS_BY_BIDDER, COLLECTION_BIDS, COLLECTION_BID_RANKING, REFUNDS, BIDS, TRADE_COUNT, TRADES, VOLUMES, LAST_SALES};
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, Addr, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Reply, ReplyOn, Response, Api,
-    StdResult, SubMsg, Uint128, WasmMsg, Coin, from_binary, BankMsg, QueryRequest, WasmQuery, Storage, Order
+    to_binary, Addr, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Reply, ReplyOn, Response, QuerierWrapper,
+    StdResult, SubMsg, Uint128, WasmMsg, from_binary, QueryRequest, WasmQuery, Storage, Order, Event
 };
 use cw2::set_contract_version;
 use cw721::{
-    OwnerOfResponse,
+    OwnerOfResponse, NftInfoResponse,
     
 };
 use cw20::Denom;
 
-use crate::constants::{ATOMPOOL, OSMOPOOL, USDCPOOL, SCRTPOOL, BLOCKATOMPOOL, BLOCKJUNOPOOL, BLOCKMARBLEPOOL, ATOMDENOM, OSMODENOM, USDCDENOM, SCRTDENOM, JUNODENOM, BLOCKADDR, MARBLEADDR};
 use cw2::{get_contract_version};
 use cw721::Cw721ReceiveMsg;
 use cw_storage_plus::Bound;
@@ -21,12 +18,12 @@ use cw721_base::{
     msg::ExecuteMsg as Cw721ExecuteMsg, msg::InstantiateMsg as Cw721InstantiateMsg, Extension, 
     msg::MintMsg, msg::BatchMintMsg, msg::QueryMsg as Cw721QueryMsg,  msg::EditMsg
 };
-use crate::msg::{ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg, MigrateMsg, SaleType, DurationType, SaleInfo, SalesResponse, Request, NftReceiveMsg};
-use cw_utils::{Expiration, Scheduled};
-use cw20::{Cw20ReceiveMsg, Cw20ExecuteMsg, Cw20CoinVerified, Balance};
-use cw_utils::parse_reply_instantiate_data;
+use crate::msg::{ConfigResponse, PendingOwnerResponse, ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg, MigrateMsg, SaleType, DurationType, SaleInfo, SalesResponse, Request, NftReceiveMsg, Royalty, AntiSniping, BidIncrement, DenomPrice, CurrentPriceResponse, WhitelistProof, PauseState, MerkleRootResponse, VerifyProvenanceResponse, RemainingTokensResponse, WhitelistMintedResponse, Offer, OffersResponse, CollectionBid, CollectionBidsResponse, BestCollectionBidResponse, PendingRefundsResponse, PoolInfo, PoolsResponse, BidderBid, BidsByBidderResponse, TradesResponse, VolumesResponse, FloorPriceResponse};
+use cw_utils::Expiration;
+use cw20::{Cw20ReceiveMsg, Balance};
+use cw_utils::{parse_reply_instantiate_data, maybe_addr};
 use sha2::Digest;
-use std::convert::TryInto;
+use wasmswap::msg::TokenSelect;
 
 use crate::util;
 
@@ -34,6 +31,7 @@ use crate::util;
 const CONTRACT_NAME: &str = "marble-collection";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 const INSTANTIATE_TOKEN_REPLY_ID: u64 = 1;
+const SWAP_REPLY_ID: u64 = 2;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -61,6 +59,13 @@ pub fn instantiate(
         return Err(crate::ContractError::ExceedsMaximumRoyaltyFee {});
     }
 
+    util::check_platform_fee(&msg.platform_fee, msg.maximum_royalty_fee)?;
+
+    if let Some(provenance_hash) = msg.provenance_hash.clone() {
+        let mut hash_buf: [u8; 32] = [0; 32];
+        hex::decode_to_slice(provenance_hash, &mut hash_buf).map_err(|_| crate::ContractError::InvalidProvenanceHash {})?;
+    }
+
     let config = Config {
         owner: msg.owner.clone(),
         cw721_address: None,
@@ -69,9 +74,16 @@ pub fn instantiate(
         symbol: msg.symbol.clone(),
         unused_token_id: 1,
         maximum_royalty_fee: msg.maximum_royalty_fee,
-        royalties: msg.royalties,
-        enabled: true,
-        uri: msg.uri
+        royalties: msg.royalties.clone(),
+        paused: PauseState::default(),
+        uri: msg.uri,
+        mint_price: msg.mint_price,
+        mint_denom: msg.mint_denom,
+        platform_fee: msg.platform_fee,
+        provenance_hash: msg.provenance_hash,
+        placeholder_uri: msg.placeholder_uri.clone(),
+        base_uri: None,
+        shuffle: msg.shuffle
     };
 
     CONFIG.save(deps.storage, &config)?;
@@ -83,6 +95,8 @@ pub fn instantiate(
                 name: msg.name.clone() + " cw721_base",
                 symbol: msg.symbol,
                 minter: env.contract.address.to_string(),
+                royalties: Some(msg.royalties),
+                placeholder_uri: msg.placeholder_uri,
             })?,
             funds: vec![],
             admin: None,
@@ -99,17 +113,21 @@ pub fn instantiate(
 
 // Reply callback triggered from cw721 contract instantiation
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, crate::ContractError> {
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, crate::ContractError> {
+    match msg.id {
+        INSTANTIATE_TOKEN_REPLY_ID => reply_instantiate_token(deps, msg),
+        SWAP_REPLY_ID => reply_swap(deps, env),
+        _ => Err(crate::ContractError::InvalidTokenReplyId {})
+    }
+}
+
+fn reply_instantiate_token(deps: DepsMut, msg: Reply) -> Result<Response, crate::ContractError> {
     let mut config: Config = CONFIG.load(deps.storage)?;
 
     if config.cw721_address != None {
         return Err(crate::ContractError::Cw721AlreadyLinked {});
     }
 
-    if msg.id != INSTANTIATE_TOKEN_REPLY_ID {
-        return Err(crate::ContractError::InvalidTokenReplyId {});
-    }
-
     let reply = parse_reply_instantiate_data(msg).unwrap();
     config.cw721_address = Addr::unchecked(reply.contract_address).into();
     CONFIG.save(deps.storage, &config)?;
@@ -118,11 +136,40 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, crate::Co
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetConfig {} => to_binary(&query_config(deps)?),
+        QueryMsg::GetPendingOwner {} => to_binary(&PendingOwnerResponse {
+            pending_owner: PENDING_OWNER.may_load(deps.storage)?
+        }),
         QueryMsg::GetSale {token_id} => to_binary(&query_get_sale(deps, token_id)?),
+        QueryMsg::GetBundleSale {bundle_id} => to_binary(&sales().load(deps.storage, bundle_key(bundle_id))?),
         QueryMsg::GetSales {start_after, limit} => to_binary(&query_get_sales(deps, start_after, limit)?),
+        QueryMsg::SalesBySeller {seller, start_after, limit} => to_binary(&query_sales_by_seller(deps, seller, start_after, limit)?),
+        QueryMsg::SalesByDenom {denom, start_after, limit} => to_binary(&query_sales_by_denom(deps, denom, start_after, limit)?),
+        QueryMsg::SalesByType {sale_type, start_after, limit} => to_binary(&query_sales_by_type(deps, sale_type, start_after, limit)?),
+        QueryMsg::SalesByPrice {sale_type, denom, min_price, max_price, start_after, limit} => to_binary(&query_sales_by_price(deps, sale_type, denom, min_price, max_price, start_after, limit)?),
+        QueryMsg::SalesEndingBefore {time, start_after, limit} => to_binary(&query_sales_ending_before(deps, time, start_after, limit)?),
+        QueryMsg::CurrentPrice {token_id} => to_binary(&query_current_price(deps, env, token_id)?),
+        QueryMsg::GetMerkleRoot {} => to_binary(&query_merkle_root(deps)?),
+        QueryMsg::GetPauseState {} => to_binary(&CONFIG.load(deps.storage)?.paused),
+        QueryMsg::RemainingTokens {} => to_binary(&query_remaining_tokens(deps)?),
+        QueryMsg::VerifyProvenance {metadata} => to_binary(&query_verify_provenance(deps, metadata)?),
+        QueryMsg::GetWhitelistMinted {address} => to_binary(&query_whitelist_minted(deps, address)?),
+        QueryMsg::GetOffers {token_id, start_after, limit} => to_binary(&query_get_offers(deps, token_id, start_after, limit)?),
+        QueryMsg::GetOffersByBidder {bidder, start_after, limit} => to_binary(&query_get_offers_by_bidder(deps, bidder, start_after, limit)?),
+        QueryMsg::GetCollectionBid {bidder} => to_binary(&query_get_collection_bid(deps, bidder)?),
+        QueryMsg::GetCollectionBids {denom, start_after, limit} => to_binary(&query_get_collection_bids(deps, denom, start_after, limit)?),
+        QueryMsg::GetBestCollectionBid {denom} => to_binary(&query_best_collection_bid(deps, denom)?),
+        QueryMsg::PendingRefunds {address} => to_binary(&query_pending_refunds(deps, address)?),
+        QueryMsg::BidsByBidder {address, start_after, limit} => to_binary(&query_bids_by_bidder(deps, address, start_after, limit)?),
+        QueryMsg::GetTrades {start_after, limit} => to_binary(&query_get_trades(deps, start_after, limit)?),
+        QueryMsg::GetVolumes {start_after, limit} => to_binary(&query_get_volumes(deps, start_after, limit)?),
+        QueryMsg::GetLastSale {token_id} => to_binary(&LAST_SALES.may_load(deps.storage, &token_id.to_string())?),
+        QueryMsg::GetLastSales {start_after, limit} => to_binary(&query_get_last_sales(deps, start_after, limit)?),
+        QueryMsg::FloorPrice {denom} => to_binary(&query_floor_price(deps, denom)?),
+        QueryMsg::GetPool {denom} => to_binary(&query_get_pool(deps, denom)?),
+        QueryMsg::GetPools {start_after, limit} => to_binary(&query_get_pools(deps, start_after, limit)?),
     }
 }
 
@@ -138,7 +185,15 @@ fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         maximum_royalty_fee: config.maximum_royalty_fee,
         royalties: config.royalties,
         uri: config.uri,
-        enabled: config.enabled
+        enabled: config.paused == PauseState::default(),
+        paused: config.paused,
+        mint_price: config.mint_price,
+        mint_denom: config.mint_denom,
+        platform_fee: config.platform_fee,
+        provenance_hash: config.provenance_hash,
+        placeholder_uri: config.placeholder_uri,
+        base_uri: config.base_uri,
+        shuffle: config.shuffle
     })
 }
 
@@ -148,7 +203,7 @@ fn query_get_sale(
     token_id: u32,
 ) -> StdResult<SaleInfo> {
 
-    let sale_info = SALE.load(deps.storage, token_id.to_string())?;
+    let sale_info = sales().load(deps.storage, token_id.to_string())?;
     Ok(sale_info)
 }
 const MAX_LIMIT: u32 = 30;
@@ -173,18 +228,455 @@ fn query_get_sales(
 
     let start = start_after.map(|str| Bound::exclusive(str.to_string()));
     
-    let sales:StdResult<Vec<_>> = SALE
+    let list:StdResult<Vec<_>> = sales()
         .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
         .map(|item| map_sales(item))
         .collect();
 
     Ok(SalesResponse {
-        list: sales?
+        list: list?
     })
     
 }
 
+fn query_sales_by_seller(
+    deps: Deps,
+    seller: String,
+    start_after: Option<String>,
+    limit: Option<u32>
+) -> StdResult<SalesResponse> {
+    let seller = deps.api.addr_validate(&seller)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let list:StdResult<Vec<_>> = sales()
+        .idx.provider
+        .prefix(seller)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| map_sales(item))
+        .collect();
+
+    Ok(SalesResponse {
+        list: list?
+    })
+}
+
+fn query_sales_by_denom(
+    deps: Deps,
+    denom: Denom,
+    start_after: Option<String>,
+    limit: Option<u32>
+) -> StdResult<SalesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let list:StdResult<Vec<_>> = sales()
+        .idx.denom
+        .prefix(util::denom_key(denom))
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| map_sales(item))
+        .collect();
+
+    Ok(SalesResponse {
+        list: list?
+    })
+}
+
+fn query_sales_by_type(
+    deps: Deps,
+    sale_type: SaleType,
+    start_after: Option<String>,
+    limit: Option<u32>
+) -> StdResult<SalesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let list:StdResult<Vec<_>> = sales()
+        .idx.sale_type
+        .prefix(sale_type_key(&sale_type))
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| map_sales(item))
+        .collect();
+
+    Ok(SalesResponse {
+        list: list?
+    })
+}
+
+// Token sales of the type listed in denom within the price range, cheapest first.
+// start_after is the (listed price, sale key) of the last sale of the previous page
+fn query_sales_by_price(
+    deps: Deps,
+    sale_type: SaleType,
+    denom: Denom,
+    min_price: Option<Uint128>,
+    max_price: Option<Uint128>,
+    start_after: Option<(Uint128, String)>,
+    limit: Option<u32>
+) -> StdResult<SalesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let index = sales().idx.price;
+    let sale_type = sale_type_key(&sale_type);
+
+    let start = match start_after {
+        Some((price, key)) => {
+            let mut raw = index.index_key(price_key(&sale_type, denom.clone(), price));
+            raw.extend_from_slice(key.as_bytes());
+            Bound::exclusive(raw)
+        },
+        None => Bound::inclusive(index.index_key(price_key(&sale_type, denom.clone(), min_price.unwrap_or_default())))
+    };
+    //Sale keys are ascii, so 0xff sorts after every sale at the max price
+    let mut end = index.index_key(price_key(&sale_type, denom, max_price.unwrap_or(Uint128::MAX)));
+    end.push(0xff);
+
+    let list:StdResult<Vec<_>> = index
+        .range(deps.storage, Some(start), Some(Bound::exclusive(end)), Order::Ascending)
+        .take(limit)
+        .map(|item| map_sales(item))
+        .collect();
+
+    Ok(SalesResponse {
+        list: list?
+    })
+}
+
+// Timed sales ending strictly before `time`, soonest first.
+// start_after is the (end time, sale key) of the last sale of the previous page
+fn query_sales_ending_before(
+    deps: Deps,
+    time: u64,
+    start_after: Option<(u64, String)>,
+    limit: Option<u32>
+) -> StdResult<SalesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let index = sales().idx.end_time;
+
+    let start = start_after.map(|(end, key)| {
+        let mut raw = index.index_key(end);
+        raw.extend_from_slice(key.as_bytes());
+        Bound::exclusive(raw)
+    });
+    let end = Some(Bound::exclusive(index.index_key(time)));
+
+    let list:StdResult<Vec<_>> = index
+        .range(deps.storage, start, end, Order::Ascending)
+        .take(limit)
+        .map(|item| map_sales(item))
+        .collect();
+
+    Ok(SalesResponse {
+        list: list?
+    })
+}
+
+fn query_current_price(
+    deps: Deps,
+    env: Env,
+    token_id: u32
+) -> StdResult<CurrentPriceResponse> {
+    let sale_info = sales().load(deps.storage, token_id.to_string())?;
+    Ok(CurrentPriceResponse {
+        token_id,
+        price: sale_price(&sale_info, env.block.time.seconds()),
+        denom: sale_info.denom
+    })
+}
+
+// Price a buyer would pay now, the highest bid for auctions that have one
+fn sale_price(
+    sale_info: &SaleInfo,
+    now: u64
+) -> Uint128 {
+    if sale_info.sale_type == SaleType::Auction && sale_info.requests.len() > 0 {
+        return sale_info.requests[sale_info.requests.len() - 1].price;
+    }
+    current_price(sale_info, now)
+}
+
+fn query_merkle_root(deps: Deps) -> StdResult<MerkleRootResponse> {
+    let merkle_root = MERKLE_ROOT.may_load(deps.storage)?;
+    Ok(MerkleRootResponse { merkle_root })
+}
+
+fn query_remaining_tokens(deps: Deps) -> StdResult<RemainingTokensResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(RemainingTokensResponse {
+        remaining: config.max_tokens.saturating_sub(config.unused_token_id)
+    })
+}
+
+// Provenance hash is sha256 of the concatenated metadata list, in token id order
+fn query_verify_provenance(
+    deps: Deps,
+    metadata: Vec<String>
+) -> StdResult<VerifyProvenanceResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let hash = hex::encode(sha2::Sha256::digest(metadata.concat().as_bytes()));
+    Ok(VerifyProvenanceResponse {
+        verified: config.provenance_hash.clone().map_or(false, |provenance_hash| provenance_hash.to_lowercase() == hash),
+        provenance_hash: config.provenance_hash
+    })
+}
+
+fn query_whitelist_minted(
+    deps: Deps,
+    address: Addr
+) -> StdResult<WhitelistMintedResponse> {
+    let minted = WHITELIST_MINTED.may_load(deps.storage, address.clone())?.unwrap_or(0);
+    Ok(WhitelistMintedResponse {
+        address,
+        minted
+    })
+}
+
+fn query_get_offers(
+    deps: Deps,
+    token_id: u32,
+    start_after: Option<String>,
+    limit: Option<u32>
+) -> StdResult<OffersResponse> {
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let start_addr = maybe_addr(deps.api, start_after)?;
+    let start = start_addr.map(|addr| Bound::exclusive(addr.as_ref()));
+
+    let offers:StdResult<Vec<_>> = OFFERS
+        .prefix(&token_id.to_string())
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_bidder, offer)| offer))
+        .collect();
+
+    Ok(OffersResponse {
+        list: offers?
+    })
+}
+
+fn query_get_offers_by_bidder(
+    deps: Deps,
+    bidder: Addr,
+    start_after: Option<u32>,
+    limit: Option<u32>
+) -> StdResult<OffersResponse> {
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let start = start_after.map(|str| Bound::exclusive(str.to_string()));
+
+    let offers:StdResult<Vec<_>> = OFFERS_BY_BIDDER
+        .prefix(&bidder)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_token_id, offer)| offer))
+        .collect();
+
+    Ok(OffersResponse {
+        list: offers?
+    })
+}
+
+fn query_get_collection_bid(
+    deps: Deps,
+    bidder: Addr
+) -> StdResult<CollectionBid> {
+    let bid = COLLECTION_BIDS.load(deps.storage, &bidder)?;
+    Ok(bid)
+}
+
+fn query_get_collection_bids(
+    deps: Deps,
+    denom: Denom,
+    start_after: Option<(Uint128, String)>,
+    limit: Option<u32>
+) -> StdResult<CollectionBidsResponse> {
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let end = start_after.map(|(price, bidder)| Bound::exclusive(rank_key(price, &bidder)));
+
+    let bidders:StdResult<Vec<_>> = COLLECTION_BID_RANKING
+        .prefix(&util::denom_key(denom))
+        .range(deps.storage, None, end, Order::Descending)
+        .take(limit)
+        .map(|item| item.map(|(_rank, bidder)| bidder))
+        .collect();
+
+    let mut list: Vec<CollectionBid> = vec![];
+    for bidder in bidders? {
+        list.push(COLLECTION_BIDS.load(deps.storage, &bidder)?);
+    }
+
+    Ok(CollectionBidsResponse {
+        list
+    })
+}
+
+fn query_best_collection_bid(
+    deps: Deps,
+    denom: Denom
+) -> StdResult<BestCollectionBidResponse> {
+    let list = query_get_collection_bids(deps, denom, None, Some(1))?.list;
+    Ok(BestCollectionBidResponse {
+        bid: list.first().cloned()
+    })
+}
+
+fn query_get_pool(
+    deps: Deps,
+    denom: Denom
+) -> StdResult<PoolInfo> {
+    let pool = POOLS.load(deps.storage, &util::denom_key(denom))?;
+    Ok(pool)
+}
+
+fn query_get_pools(
+    deps: Deps,
+    start_after: Option<Denom>,
+    limit: Option<u32>
+) -> StdResult<PoolsResponse> {
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let start = start_after.map(|denom| Bound::exclusive(util::denom_key(denom)));
+
+    let pools:StdResult<Vec<_>> = POOLS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_denom, pool)| pool))
+        .collect();
+
+    Ok(PoolsResponse {
+        list: pools?
+    })
+}
+
+fn query_pending_refunds(
+    deps: Deps,
+    address: Addr
+) -> StdResult<PendingRefundsResponse> {
+    let refunds:StdResult<Vec<_>> = REFUNDS
+        .prefix(&address)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_denom, refund)| refund))
+        .collect();
+
+    Ok(PendingRefundsResponse {
+        address,
+        refunds: refunds?
+    })
+}
+
+fn query_bids_by_bidder(
+    deps: Deps,
+    address: Addr,
+    start_after: Option<String>,
+    limit: Option<u32>
+) -> StdResult<BidsByBidderResponse> {
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let start = start_after.map(Bound::exclusive);
+
+    let bids:StdResult<Vec<_>> = BIDS
+        .prefix(&address)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(key, request)| BidderBid {
+            token_id: key,
+            amount: request.amount,
+            denom: request.denom
+        }))
+        .collect();
+
+    Ok(BidsByBidderResponse {
+        address: address.clone(),
+        list: bids?
+    })
+}
+
+fn query_get_trades(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>
+) -> StdResult<TradesResponse> {
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let end = start_after.map(Bound::exclusive_int);
+
+    let trades:StdResult<Vec<_>> = TRADES
+        .range(deps.storage, None, end, Order::Descending)
+        .take(limit)
+        .map(|item| item.map(|(_id, trade)| trade))
+        .collect();
+
+    Ok(TradesResponse {
+        list: trades?
+    })
+}
+
+fn query_get_volumes(
+    deps: Deps,
+    start_after: Option<Denom>,
+    limit: Option<u32>
+) -> StdResult<VolumesResponse> {
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let start = start_after.map(|denom| Bound::exclusive(util::denom_key(denom)));
+
+    let volumes:StdResult<Vec<_>> = VOLUMES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_denom, volume)| volume))
+        .collect();
+
+    Ok(VolumesResponse {
+        trade_count: TRADE_COUNT.may_load(deps.storage)?.unwrap_or(0),
+        list: volumes?
+    })
+}
+
+fn query_get_last_sales(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>
+) -> StdResult<TradesResponse> {
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let start = start_after.map(Bound::exclusive);
+
+    let trades:StdResult<Vec<_>> = LAST_SALES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_token_id, trade)| trade))
+        .collect();
+
+    Ok(TradesResponse {
+        list: trades?
+    })
+}
+
+fn query_floor_price(
+    deps: Deps,
+    denom: Denom
+) -> StdResult<FloorPriceResponse> {
+    let floor = query_sales_by_price(deps, SaleType::Fixed, denom.clone(), None, None, None, Some(1))?.list;
+
+    Ok(FloorPriceResponse {
+        denom,
+        token_id: floor.first().map(|sale_info| sale_info.token_id),
+        price: floor.first().map(|sale_info| sale_info.initial_price)
+    })
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -193,24 +685,52 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, crate::ContractError> {
     match msg {
-        ExecuteMsg::UpdateOwner { owner } => util::execute_update_owner(deps.storage, info.sender, owner),
+        ExecuteMsg::UpdateOwner { owner, expires } => util::execute_update_owner(deps.storage, deps.api, info.sender, owner, expires),
+        ExecuteMsg::AcceptOwnership {} => util::execute_accept_ownership(deps.storage, &env.block, info.sender),
+        ExecuteMsg::CancelOwnershipTransfer {} => util::execute_cancel_ownership_transfer(deps.storage, info.sender),
         ExecuteMsg::UpdateEnabled { enabled } => util::execute_update_enabled(deps.storage, info.sender, enabled),
+        ExecuteMsg::UpdatePauseState { paused } => util::execute_update_pause_state(deps.storage, info.sender, paused),
         ExecuteMsg::UpdateRoyalties { maximum_royalty_fee, royalties } => util::execute_update_royalties(deps.storage, info.sender, maximum_royalty_fee, royalties),
-        ExecuteMsg::ReceiveNft(msg) => execute_receive_nft(deps, info, msg),
+        ExecuteMsg::UpdateMintPrice { mint_price, mint_denom } => util::execute_update_mint_price(deps.storage, info.sender, mint_price, mint_denom),
+        ExecuteMsg::AddPool { denom, pool_address } => util::execute_add_pool(deps.storage, deps.querier, info.sender, denom, pool_address),
+        ExecuteMsg::RemovePool { denom } => util::execute_remove_pool(deps.storage, info.sender, denom),
+        ExecuteMsg::ReceiveNft(msg) => execute_receive_nft(deps, env, info, msg),
         ExecuteMsg::AcceptSale { token_id } => {
-            execute_accept_sale(deps, info, token_id)
+            execute_accept_sale(deps, env, info, token_id.to_string())
         },
         ExecuteMsg::CancelSale { token_id } => {
-            execute_cancel_sale(deps, info, token_id)
+            execute_cancel_sale(deps, info, token_id.to_string())
+        },
+        ExecuteMsg::SettleSale { token_id } => {
+            execute_settle_sale(deps, env, token_id.to_string())
         },
-        ExecuteMsg::Mint{ uri, extension } => {
-            execute_mint(deps, env, info, uri, extension)
+        ExecuteMsg::StartBundleSale {
+            token_ids,
+            sale_type,
+            duration_type,
+            initial_price,
+            reserve_price,
+            denom,
+            anti_sniping,
+            min_increment,
+            prices,
+            accepted_denoms
+        } => execute_start_bundle_sale(deps, env, info, token_ids, sale_type, duration_type, initial_price, reserve_price, denom, anti_sniping, min_increment, prices, accepted_denoms),
+        ExecuteMsg::ProposeBundle { bundle_id, denom } => execute_propose(deps, env, info, bundle_key(bundle_id), denom),
+        ExecuteMsg::AcceptBundleSale { bundle_id } => execute_accept_sale(deps, env, info, bundle_key(bundle_id)),
+        ExecuteMsg::CancelBundleSale { bundle_id } => execute_cancel_sale(deps, info, bundle_key(bundle_id)),
+        ExecuteMsg::SettleBundleSale { bundle_id } => execute_settle_sale(deps, env, bundle_key(bundle_id)),
+        ExecuteMsg::CancelBundlePropose { bundle_id } => execute_cancel_propose(deps, env, info, bundle_key(bundle_id)),
+        ExecuteMsg::RegisterMerkleRoot { merkle_root } => execute_register_merkle_root(deps, info, merkle_root),
+        ExecuteMsg::Mint{ uri, extension, whitelist } => {
+            execute_mint(deps, env, info, uri, extension, whitelist)
         },
         ExecuteMsg::BatchMint{ uri, extension, owner} => {
             execute_batch_mint(deps, env, info, uri, extension, owner)
         },
 
-        ExecuteMsg::Propose{token_id, denom} => execute_propose(deps, env, info, token_id, denom),
+        ExecuteMsg::Propose{token_id, denom} => execute_propose(deps, env, info, token_id.to_string(), denom),
+        ExecuteMsg::SwapPropose{token_id, denom, min_output} => execute_swap_propose(deps, env, info, token_id, denom, min_output),
         ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
         
         
@@ -223,15 +743,27 @@ pub fn execute(
         ExecuteMsg::UpdateUnusedTokenId {
             token_id
         } => execute_update_unused_token_id(deps, info, token_id),
+        ExecuteMsg::Reveal { base_uri } => execute_reveal(deps, info, base_uri),
+        ExecuteMsg::Edit { token_id, uri, extension } => execute_edit(deps, info, token_id, uri, extension),
+        ExecuteMsg::FreezeMetadata { token_id } => execute_freeze_metadata(deps, info, token_id),
         ExecuteMsg::EditSale {
             token_id,
             sale_type,
             duration_type,
             initial_price,
             reserve_price,
-            denom
-        } => execute_edit_sale(deps, info, token_id, sale_type, duration_type, initial_price, reserve_price, denom),
-        ExecuteMsg::CancelPropose { token_id } => execute_cancel_propose(deps, info, token_id)
+            denom,
+            anti_sniping,
+            min_increment,
+            prices,
+            accepted_denoms
+        } => execute_edit_sale(deps, info, token_id, sale_type, duration_type, initial_price, reserve_price, denom, anti_sniping, min_increment, prices, accepted_denoms),
+        ExecuteMsg::CancelPropose { token_id } => execute_cancel_propose(deps, env, info, token_id.to_string()),
+        ExecuteMsg::Offer { token_id, denom, expires } => execute_offer(deps, env, info, token_id, denom, expires),
+        ExecuteMsg::CancelOffer { token_id } => execute_cancel_offer(deps, info, token_id),
+        ExecuteMsg::CollectionBid { denom } => execute_collection_bid(deps, info, denom),
+        ExecuteMsg::CancelCollectionBid {} => execute_cancel_collection_bid(deps, info),
+        ExecuteMsg::WithdrawRefunds {} => execute_withdraw_refunds(deps, info)
 
     }
 }
@@ -242,9 +774,31 @@ pub fn execute_mint(
     env: Env,
     info: MessageInfo,
     uri: String,
-    extension: Extension
+    extension: Extension,
+    whitelist: Option<WhitelistProof>
+) -> Result<Response, crate::ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let mut amount = Uint128::zero();
+    if config.mint_price > Uint128::zero() {
+        amount = util::get_amount_of_denom(Balance::from(info.funds), config.mint_denom.clone())?;
+    } else if info.funds.len() > 0 {
+        return Err(crate::ContractError::IncorrectFunds {});
+    }
+
+    handle_mint(deps, env, info.sender.clone(), uri, extension, whitelist, amount)
+}
+
+pub fn handle_mint(
+    deps: DepsMut,
+    env: Env,
+    minter: Addr,
+    uri: String,
+    extension: Extension,
+    whitelist: Option<WhitelistProof>,
+    amount: Uint128
 ) -> Result<Response, crate::ContractError> {
-    util::check_enabled(deps.storage)?;
+    util::check_enabled(deps.storage, util::Activity::Minting)?;
     let mut config = CONFIG.load(deps.storage)?;
     
     if config.cw721_address == None {
@@ -255,23 +809,100 @@ pub fn execute_mint(
         return Err(crate::ContractError::MaxTokensExceed {});
     }
 
+    if amount != config.mint_price {
+        return Err(crate::ContractError::WrongPaymentAmount {});
+    }
+
+    check_whitelist(deps.storage, minter.clone(), whitelist)?;
+    util::check_extension_royalties(deps.storage, &extension)?;
+
+    let token_id = match config.shuffle {
+        true => util::pick_token_id(deps.storage, &env, &minter, &config)?,
+        false => config.unused_token_id
+    };
     let mint_msg = Cw721ExecuteMsg::Mint(MintMsg::<Extension> {
-        token_id: config.unused_token_id.to_string(),
-        owner: info.sender.clone().into(),
-        token_uri: uri.clone().into(),
+        token_id: token_id.to_string(),
+        owner: minter.clone().into(),
+        token_uri: mint_uri(&config, uri).into(),
         extension: extension.clone(),
     });
 
-    let callback = CosmosMsg::Wasm(WasmMsg::Execute {
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
         contract_addr: config.cw721_address.clone().unwrap().to_string(),
         msg: to_binary(&mint_msg)?,
         funds: vec![],
-    });
+    }));
+
+    //Split mint proceeds between royalties and collection owner
+    msgs.append(&mut royalty_messages(config.royalties.clone(), config.mint_denom.clone(), amount, config.owner.clone())?);
 
     config.unused_token_id += 1;
     CONFIG.save(deps.storage, &config)?;
 
-    Ok(Response::new().add_message(callback))
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "mint")
+        .add_attribute("address", minter)
+        .add_attribute("token_id", token_id.to_string())
+        .add_attribute("price", amount)
+    )
+}
+
+// While a merkle root is registered only whitelisted addresses can mint, up to their allowance
+fn check_whitelist(
+    storage: &mut dyn Storage,
+    address: Addr,
+    whitelist: Option<WhitelistProof>
+) -> Result<(), crate::ContractError> {
+    let merkle_root = match MERKLE_ROOT.may_load(storage)? {
+        Some(merkle_root) => merkle_root,
+        None => return Ok(())
+    };
+    let whitelist = whitelist.ok_or(crate::ContractError::WhitelistRequired {})?;
+
+    util::verify_merkle_proof(merkle_root, format!("{}{}", address, whitelist.amount), whitelist.proof)?;
+
+    let minted = WHITELIST_MINTED.may_load(storage, address.clone())?.unwrap_or(0);
+    if minted >= whitelist.amount {
+        return Err(crate::ContractError::MintAllowanceExceeded {});
+    }
+    WHITELIST_MINTED.save(storage, address, &(minted + 1))?;
+    Ok(())
+}
+
+// Tokens minted before the reveal get the placeholder
+fn mint_uri(
+    config: &Config,
+    uri: String
+) -> String {
+    match (config.placeholder_uri.clone(), config.base_uri.clone()) {
+        (Some(placeholder_uri), None) => placeholder_uri,
+        _ => uri
+    }
+}
+
+pub fn execute_register_merkle_root(
+    deps: DepsMut,
+    info: MessageInfo,
+    merkle_root: Option<String>
+) -> Result<Response, crate::ContractError> {
+    util::check_owner(deps.storage, info.sender.clone())?;
+
+    match merkle_root.clone() {
+        Some(root) => {
+            // check merkle root length
+            let mut root_buf: [u8; 32] = [0; 32];
+            hex::decode_to_slice(root.clone(), &mut root_buf)?;
+            MERKLE_ROOT.save(deps.storage, &root)?;
+        },
+        None => MERKLE_ROOT.remove(deps.storage)
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "register_merkle_root")
+        .add_attribute("merkle_root", merkle_root.unwrap_or_default())
+    )
 }
 
 
@@ -283,7 +914,7 @@ pub fn execute_batch_mint(
     extension: Vec<Extension>,
     owner: Vec<String>
 ) -> Result<Response, crate::ContractError> {
-    util::check_enabled(deps.storage)?;
+    util::check_enabled(deps.storage, util::Activity::Minting)?;
     let mut config = CONFIG.load(deps.storage)?;
     if info.sender != config.owner {
         return Err(crate::ContractError::Unauthorized {});
@@ -301,17 +932,24 @@ pub fn execute_batch_mint(
         return Err(crate::ContractError::MaxTokensExceed {});
     }
 
+    for item in extension.iter() {
+        util::check_extension_royalties(deps.storage, item)?;
+    }
+
     let count = uri.len();
     let mut token_id:Vec<String> = vec![];
     for _i in 0..count {
-        token_id.push(config.unused_token_id.to_string());
+        match config.shuffle {
+            true => token_id.push(util::pick_token_id(deps.storage, &env, &info.sender, &config)?.to_string()),
+            false => token_id.push(config.unused_token_id.to_string())
+        }
         config.unused_token_id += 1;
     }
     
     let mint_msg = Cw721ExecuteMsg::BatchMint(BatchMintMsg::<Extension> {
         token_id,
         owner,
-        token_uri: uri,
+        token_uri: uri.into_iter().map(|item| mint_uri(&config, item)).collect(),
         extension: extension.clone(),
     });
 
@@ -329,10 +967,10 @@ pub fn execute_batch_mint(
 
 pub fn execute_receive_nft(
     deps: DepsMut, 
+    env: Env,
     info: MessageInfo, 
     wrapper: Cw721ReceiveMsg
 ) -> Result<Response, crate::ContractError> {
-    util::check_enabled(deps.storage)?;
     let cfg = CONFIG.load(deps.storage)?;
 
     if info.sender.clone() != cfg.cw721_address.clone().unwrap() {
@@ -343,27 +981,17 @@ pub fn execute_receive_nft(
     let user_addr = deps.api.addr_validate(wrapper.sender.as_str())?;
 
     let msg: NftReceiveMsg = from_binary(&wrapper.msg)?;
+    match msg {
+        NftReceiveMsg::StartSale {..} => util::check_enabled(deps.storage, util::Activity::Listing)?,
+        _ => util::check_enabled(deps.storage, util::Activity::Settlement)?
+    };
 
-    if SALE.has(deps.storage, token_id.clone()) {
+    if sales().may_load(deps.storage, token_id.clone())?.is_some() {
         return Err(crate::ContractError::AlreadyOnSale {});
     }
 
     match msg {
-        NftReceiveMsg::StartSale {sale_type, duration_type, initial_price, reserve_price, denom} => {
-            if sale_type == SaleType::Fixed && duration_type != DurationType::Fixed {
-                return Err(crate::ContractError::InvalidSaleType {});
-            }
-            
-            match duration_type.clone() {
-                DurationType::Time(start, end) => {
-                    if start >= end {
-                        return Err(crate::ContractError::DurationIncorrect {});
-                    }
-                },
-                DurationType::Fixed => {},
-                DurationType::Bid(_count) => {}
-            }
-        
+        NftReceiveMsg::StartSale {sale_type, duration_type, initial_price, reserve_price, denom, anti_sniping, min_increment, prices, accepted_denoms} => {
             let info = SaleInfo {
                 token_id: token_id.parse().unwrap(),
                 provider: user_addr.clone(),
@@ -372,34 +1000,246 @@ pub fn execute_receive_nft(
                 initial_price,
                 reserve_price,
                 requests: vec![],
+                bid_count: 0,
                 denom,
-                can_accept: false
+                can_accept: false,
+                anti_sniping,
+                min_increment,
+                prices,
+                accepted_denoms,
+                bundle: None
             };
+            check_sale_info(&info)?;
             
-            SALE.save(deps.storage, token_id.clone(), &info)?;
+            sales().save(deps.storage, token_id.clone(), &info)?;
             Ok(Response::new()
                 .add_attribute("action", "start_sale")
                 .add_attribute("token_id", token_id.clone())
                 .add_attribute("initial_price", initial_price)
                 .add_attribute("reserve_price", reserve_price)
             )
+        },
+        NftReceiveMsg::AcceptOffer { bidder } => {
+            let bidder = deps.api.addr_validate(&bidder)?;
+            let offer = OFFERS.may_load(deps.storage, (token_id.as_str(), &bidder))?
+                .ok_or(crate::ContractError::OfferNotFound {})?;
+            if offer.expires.is_expired(&env.block) {
+                return Err(crate::ContractError::AlreadyExpired {});
+            }
+
+            OFFERS.remove(deps.storage, (token_id.as_str(), &bidder));
+            OFFERS_BY_BIDDER.remove(deps.storage, (&bidder, token_id.as_str()));
+
+            let (msgs, platform_fee) = sell_nft_messages(deps.storage, deps.querier, bidder.clone(), offer.price, vec![offer.token_id], user_addr.clone(), offer.denom.clone())?;
+            util::record_trade(deps.storage, env.block.time.seconds(), vec![offer.token_id], user_addr.clone(), bidder.clone(), offer.price, offer.denom.clone())?;
+
+            Ok(Response::new()
+                .add_messages(msgs)
+                .add_attribute("action", "accept_offer")
+                .add_attribute("token_id", token_id.clone())
+                .add_attribute("address", bidder.to_string())
+                .add_attribute("price", offer.price)
+                .add_attribute("platform_fee", platform_fee)
+            )
+        },
+        NftReceiveMsg::AcceptCollectionBid { bidder } => {
+            let bidder = deps.api.addr_validate(&bidder)?;
+            let bid = COLLECTION_BIDS.may_load(deps.storage, &bidder)?
+                .ok_or(crate::ContractError::OfferNotFound {})?;
+
+            remove_collection_bid(deps.storage, &bid);
+
+            let (msgs, platform_fee) = sell_nft_messages(deps.storage, deps.querier, bidder.clone(), bid.price, vec![token_id.parse().unwrap()], user_addr.clone(), bid.denom.clone())?;
+            util::record_trade(deps.storage, env.block.time.seconds(), vec![token_id.parse().unwrap()], user_addr.clone(), bidder.clone(), bid.price, bid.denom.clone())?;
+
+            Ok(Response::new()
+                .add_messages(msgs)
+                .add_attribute("action", "accept_collection_bid")
+                .add_attribute("token_id", token_id.clone())
+                .add_attribute("address", bidder.to_string())
+                .add_attribute("price", bid.price)
+                .add_attribute("platform_fee", platform_fee)
+            )
         }
     }
 }
 
+// Escrows tokens the collection is approved for and lists them as one lot
+pub fn execute_start_bundle_sale(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_ids: Vec<u32>,
+    sale_type: SaleType,
+    duration_type: DurationType,
+    initial_price: Uint128,
+    reserve_price: Uint128,
+    denom: Denom,
+    anti_sniping: Option<AntiSniping>,
+    min_increment: Option<BidIncrement>,
+    prices: Vec<DenomPrice>,
+    accepted_denoms: Vec<Denom>
+) -> Result<Response, crate::ContractError> {
+    util::check_enabled(deps.storage, util::Activity::Listing)?;
+    let cfg = CONFIG.load(deps.storage)?;
+    let cw721_address = cfg.cw721_address.clone().ok_or(crate::ContractError::Uninitialized {})?;
+
+    if token_ids.len() < 2 {
+        return Err(crate::ContractError::InvalidBundle {});
+    }
+
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    for i in 0..token_ids.len() {
+        if token_ids[..i].contains(&token_ids[i]) {
+            return Err(crate::ContractError::InvalidBundle {});
+        }
+        let owner: OwnerOfResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: cw721_address.to_string(),
+            msg: to_binary(&Cw721QueryMsg::OwnerOf {
+                token_id: token_ids[i].to_string(),
+                include_expired: None
+            })?,
+        }))?;
+        if owner.owner != info.sender.to_string() {
+            return Err(crate::ContractError::Unauthorized {});
+        }
+        msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: cw721_address.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw721ExecuteMsg::<Extension>::TransferNft {
+                recipient: env.contract.address.to_string(),
+                token_id: token_ids[i].to_string()
+            })?,
+        }));
+    }
+
+    let bundle_id = BUNDLE_COUNT.may_load(deps.storage)?.unwrap_or(0) + 1;
+    BUNDLE_COUNT.save(deps.storage, &bundle_id)?;
+
+    let sale_info = SaleInfo {
+        token_id: bundle_id,
+        provider: info.sender.clone(),
+        sale_type,
+        duration_type,
+        initial_price,
+        reserve_price,
+        requests: vec![],
+        bid_count: 0,
+        denom,
+        can_accept: false,
+        anti_sniping,
+        min_increment,
+        prices,
+        accepted_denoms,
+        bundle: Some(token_ids)
+    };
+    check_sale_info(&sale_info)?;
+
+    sales().save(deps.storage, bundle_key(bundle_id), &sale_info)?;
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "start_bundle_sale")
+        .add_attribute("bundle_id", bundle_id.to_string())
+        .add_attribute("initial_price", initial_price)
+        .add_attribute("reserve_price", reserve_price)
+    )
+}
+
+// SALE key of bundles, token sales are keyed by their token id
+pub fn bundle_key(
+    bundle_id: u32
+) -> String {
+    format!("bundle_{}", bundle_id)
+}
+
+pub fn sale_key(
+    sale_info: &SaleInfo
+) -> String {
+    match sale_info.bundle {
+        Some(_) => bundle_key(sale_info.token_id),
+        None => sale_info.token_id.to_string()
+    }
+}
+
+// Tokens sold by the sale, for bundles token_id is the bundle id
+pub fn sale_token_ids(
+    sale_info: &SaleInfo
+) -> Vec<u32> {
+    match sale_info.bundle.clone() {
+        Some(token_ids) => token_ids,
+        None => vec![sale_info.token_id]
+    }
+}
+
+fn check_sale_info(
+    sale_info: &SaleInfo
+) -> Result<(), crate::ContractError> {
+    if sale_info.sale_type == SaleType::Fixed && sale_info.duration_type != DurationType::Fixed {
+        return Err(crate::ContractError::InvalidSaleType {});
+    }
+    
+    match sale_info.duration_type.clone() {
+        DurationType::Time(start, end) => {
+            if start >= end {
+                return Err(crate::ContractError::DurationIncorrect {});
+            }
+        },
+        DurationType::Fixed => {},
+        DurationType::Bid(_count) => {}
+    }
+
+    if let Some(anti_sniping) = sale_info.anti_sniping.clone() {
+        match sale_info.duration_type.clone() {
+            DurationType::Time(_start, _end) => {},
+            _ => return Err(crate::ContractError::DurationIncorrect {})
+        }
+        if sale_info.sale_type != SaleType::Auction || anti_sniping.extension == 0 {
+            return Err(crate::ContractError::InvalidAntiSniping {});
+        }
+    }
+
+    if sale_info.sale_type == SaleType::Dutch {
+        match sale_info.duration_type.clone() {
+            DurationType::Time(_start, _end) => {},
+            _ => return Err(crate::ContractError::DurationIncorrect {})
+        }
+        if sale_info.initial_price < sale_info.reserve_price {
+            return Err(crate::ContractError::InvalidUnitPrice {});
+        }
+    }
+
+    if sale_info.min_increment.is_some() && sale_info.sale_type != SaleType::Auction {
+        return Err(crate::ContractError::InvalidSaleType {});
+    }
+
+    if sale_info.prices.len() > 0 && sale_info.sale_type != SaleType::Fixed
+        || sale_info.accepted_denoms.len() > 0 && sale_info.sale_type != SaleType::Auction {
+        return Err(crate::ContractError::InvalidSaleType {});
+    }
+    let mut denoms: Vec<Denom> = vec![sale_info.denom.clone()];
+    for denom in sale_info.prices.iter().map(|item| item.denom.clone()).chain(sale_info.accepted_denoms.clone()) {
+        if denoms.contains(&denom) {
+            return Err(crate::ContractError::InvalidBuyParam {});
+        }
+        denoms.push(denom);
+    }
+    Ok(())
+}
+
 pub fn execute_accept_sale(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    token_id: u32
+    key: String
 ) -> Result<Response, crate::ContractError> {
 
-    util::check_enabled(deps.storage)?;
+    util::check_enabled(deps.storage, util::Activity::Settlement)?;
 
-    if !SALE.has(deps.storage, token_id.to_string()) {
+    if sales().may_load(deps.storage, key.clone())?.is_none() {
         return Err(crate::ContractError::NotOnSale {});
     }
     
-    let sale_info = SALE.load(deps.storage, token_id.to_string())?;
+    let sale_info = sales().load(deps.storage, key.clone())?;
     
     if sale_info.provider != info.sender {
         return Err(crate::ContractError::Unauthorized {  });
@@ -409,42 +1249,111 @@ pub fn execute_accept_sale(
         return Err(crate::ContractError::NoBids {});
     }
     
+    complete_auction(deps, env, sale_info, "accept_sale")
+}
+
+pub fn execute_settle_sale(
+    deps: DepsMut,
+    env: Env,
+    key: String
+) -> Result<Response, crate::ContractError> {
+
+    util::check_enabled(deps.storage, util::Activity::Settlement)?;
+
+    if sales().may_load(deps.storage, key.clone())?.is_none() {
+        return Err(crate::ContractError::NotOnSale {});
+    }
+    
+    let sale_info = sales().load(deps.storage, key.clone())?;
+
+    if sale_info.sale_type != SaleType::Auction {
+        return Err(crate::ContractError::InvalidSaleType {});
+    }
+
+    match sale_info.duration_type.clone() {
+        DurationType::Fixed => {
+            return Err(crate::ContractError::NotSupported {});
+        },
+        DurationType::Time(_start, end) => {
+            if env.block.time.seconds() <= end {
+                return Err(crate::ContractError::NotExpired {});
+            }
+        },
+        DurationType::Bid(threshold) => {
+            if sale_info.bid_count < threshold {
+                return Err(crate::ContractError::NotExpired {});
+            }
+        }
+    }
+
+    //No bid reached the reserve, give the NFT back and refund the bidders
+    if !sale_info.can_accept {
+        let msgs = unwind_sale_messages(deps.storage, sale_info.clone())?;
+        sales().remove(deps.storage, key.clone())?;
+
+        return Ok(Response::new()
+            .add_messages(msgs)
+            .add_event(Event::new("reserve_not_met")
+                .add_attribute("token_id", key.clone())
+                .add_attribute("provider", sale_info.provider.to_string())
+                .add_attribute("reserve_price", sale_info.reserve_price)
+            )
+            .add_attribute("action", "settle_sale")
+            .add_attribute("token_id", key.clone())
+        );
+    }
+
+    complete_auction(deps, env, sale_info, "settle_sale")
+}
+
+// Sells the NFT to the highest bid and credits the funds of the other bidders
+fn complete_auction(
+    deps: DepsMut,
+    env: Env,
+    sale_info: SaleInfo,
+    action: &str
+) -> Result<Response, crate::ContractError> {
+    let key = sale_key(&sale_info);
     let list = sale_info.requests.clone();
     let len = sale_info.requests.len();
     let sell_request = list.get(len - 1).unwrap();
     //Add NFT send msg
-    let mut msgs = sell_nft_messages(deps.storage, deps.api, sell_request.address.clone(), sell_request.price.clone(), sale_info.clone())?;
+    let (msgs, platform_fee) = sell_nft_messages(deps.storage, deps.querier, sell_request.address.clone(), sell_request.amount, sale_token_ids(&sale_info), sale_info.provider.clone(), sell_request.denom.clone())?;
 
-    //Add return fund msg
+    //Credit the other bidders
     for i in 0..len - 1 {
         let request = list.get(i).unwrap();
-        msgs.push(util::transfer_token_message(sale_info.denom.clone(), request.price, request.address.clone())?);
+        util::credit_refund(deps.storage, request.address.clone(), request.denom.clone(), request.amount)?;
+    }
+    for request in list.iter() {
+        BIDS.remove(deps.storage, (&request.address, &key));
     }
+    util::record_trade(deps.storage, env.block.time.seconds(), sale_token_ids(&sale_info), sale_info.provider.clone(), sell_request.address.clone(), sell_request.amount, sell_request.denom.clone())?;
     
-    SALE.remove(deps.storage, token_id.to_string());
+    sales().remove(deps.storage, key.clone())?;
 
     Ok(Response::new()
         .add_messages(msgs)
-        .add_attribute("action", "accept_sale")
-        .add_attribute("token_id", token_id.to_string())
+        .add_attribute("action", action)
+        .add_attribute("token_id", key.clone())
         .add_attribute("address", sell_request.address.clone().to_string())
         .add_attribute("price", sell_request.price)
+        .add_attribute("platform_fee", platform_fee)
     )
 }
 
 pub fn execute_cancel_sale(
     deps: DepsMut,
     info: MessageInfo,
-    token_id: u32
+    key: String
 ) -> Result<Response, crate::ContractError> {
 
-    util::check_enabled(deps.storage)?;
-
-    if !SALE.has(deps.storage, token_id.to_string()) {
+    //Not pausable, sellers can always take their NFT back
+    if sales().may_load(deps.storage, key.clone())?.is_none() {
         return Err(crate::ContractError::NotOnSale {});
     }
     
-    let sale_info = SALE.load(deps.storage, token_id.to_string())?;
+    let sale_info = sales().load(deps.storage, key.clone())?;
 
     if sale_info.provider != info.sender {
         return Err(crate::ContractError::Unauthorized {  });
@@ -454,32 +1363,46 @@ pub fn execute_cancel_sale(
         return Err(crate::ContractError::CannotCancelSale {  });
     }
 
-    let cfg = CONFIG.load(deps.storage)?;
+    let msgs = unwind_sale_messages(deps.storage, sale_info)?;
+
+    sales().remove(deps.storage, key.clone())?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "cancel_sale")
+        .add_attribute("token_id", key.clone())
+    )
+}
+
+// Returns the NFT to the provider and credits every bid to the refund ledger
+fn unwind_sale_messages(
+    storage: &mut dyn Storage,
+    sale_info: SaleInfo
+) -> Result<Vec<CosmosMsg>, crate::ContractError> {
+    let cfg = CONFIG.load(storage)?;
 
     let mut msgs: Vec<CosmosMsg> = vec![];
-    msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: cfg.cw721_address.clone().unwrap().to_string(),
-        funds: vec![],
-        msg: to_binary(&Cw721ExecuteMsg::<Extension>::TransferNft {
-            recipient: info.sender.clone().into(),
-            token_id: sale_info.token_id.to_string()
-        })?,
-    }));
+    for token_id in sale_token_ids(&sale_info) {
+        msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: cfg.cw721_address.clone().unwrap().to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw721ExecuteMsg::<Extension>::TransferNft {
+                recipient: sale_info.provider.clone().into(),
+                token_id: token_id.to_string()
+            })?,
+        }));
+    }
 
+    let key = sale_key(&sale_info);
     let list = sale_info.requests.clone();
-    //Add return fund msg
+    //Credit the bidders
     for i in 0..list.len() {
         let request = list.get(i).unwrap();
-        msgs.push(util::transfer_token_message(sale_info.denom.clone(), request.price, request.address.clone())?);
+        util::credit_refund(storage, request.address.clone(), request.denom.clone(), request.amount)?;
+        BIDS.remove(storage, (&request.address, &key));
     }
 
-    SALE.remove(deps.storage, token_id.to_string());
-
-    Ok(Response::new()
-        .add_messages(msgs)
-        .add_attribute("action", "cancel_sale")
-        .add_attribute("token_id", token_id.to_string())
-    )
+    Ok(msgs)
 }
 
 
@@ -487,18 +1410,18 @@ pub fn execute_propose(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    token_id: u32,
+    key: String,
     denom: String 
 ) -> Result<Response, crate::ContractError> {
 
-    let sale_info = SALE.load(deps.storage, token_id.to_string())?;
-    if sale_info.denom != Denom::Native(denom.clone()) {
+    let sale_info = sales().load(deps.storage, key.clone())?;
+    if !accepts_denom(&sale_info, &Denom::Native(denom.clone())) {
         return Err(crate::ContractError::InvalidNativeToken {})
     }
 
     let amount = util::get_amount_of_denom(Balance::from(info.funds), Denom::Native(denom.clone()))?;
 
-    handle_propose(deps, env, token_id, info.sender.clone(), amount)
+    handle_propose(deps, env, key, info.sender.clone(), amount, Denom::Native(denom))
     
 }
 
@@ -517,11 +1440,36 @@ pub fn execute_receive(
     match msg {
         ReceiveMsg::Propose { token_id } => {
 
-            let sale_info = SALE.load(deps.storage, token_id.to_string())?;
-            if sale_info.denom != Denom::Cw20(info.sender.clone()) {
+            let sale_info = sales().load(deps.storage, token_id.to_string())?;
+            if !accepts_denom(&sale_info, &Denom::Cw20(info.sender.clone())) {
                 return Err(crate::ContractError::InvalidCw20Token {})
             }
-            handle_propose(deps, env, token_id, user_addr.clone(), cw20_amount)
+            handle_propose(deps, env, token_id.to_string(), user_addr.clone(), cw20_amount, Denom::Cw20(info.sender.clone()))
+        },
+        ReceiveMsg::ProposeBundle { bundle_id } => {
+
+            let sale_info = sales().load(deps.storage, bundle_key(bundle_id))?;
+            if !accepts_denom(&sale_info, &Denom::Cw20(info.sender.clone())) {
+                return Err(crate::ContractError::InvalidCw20Token {})
+            }
+            handle_propose(deps, env, bundle_key(bundle_id), user_addr.clone(), cw20_amount, Denom::Cw20(info.sender.clone()))
+        },
+        ReceiveMsg::SwapPropose { token_id, min_output } => {
+            handle_swap_propose(deps, env, token_id, user_addr.clone(), cw20_amount, Denom::Cw20(info.sender.clone()), min_output)
+        },
+        ReceiveMsg::Mint { uri, extension, whitelist } => {
+
+            let config = CONFIG.load(deps.storage)?;
+            if config.mint_denom != Denom::Cw20(info.sender.clone()) {
+                return Err(crate::ContractError::InvalidCw20Token {})
+            }
+            handle_mint(deps, env, user_addr.clone(), uri, extension, whitelist, cw20_amount)
+        },
+        ReceiveMsg::Offer { token_id, expires } => {
+            handle_offer(deps, env, token_id, user_addr.clone(), cw20_amount, Denom::Cw20(info.sender.clone()), expires)
+        },
+        ReceiveMsg::CollectionBid {} => {
+            handle_collection_bid(deps, user_addr.clone(), cw20_amount, Denom::Cw20(info.sender.clone()))
         }
     }
 }
@@ -529,16 +1477,27 @@ pub fn execute_receive(
 pub fn handle_propose(
     deps: DepsMut,
     env: Env,
-    token_id: u32,
+    key: String,
     address: Addr, 
-    price: Uint128
+    amount: Uint128,
+    denom: Denom
 ) -> Result<Response, crate::ContractError> {
 
-    util::check_enabled(deps.storage)?;
-    if !SALE.has(deps.storage, token_id.to_string()) {
+    util::check_enabled(deps.storage, util::Activity::Bidding)?;
+    if sales().may_load(deps.storage, key.clone())?.is_none() {
         return Err(crate::ContractError::NotOnSale {});
     }
-    let mut sale_info = SALE.load(deps.storage, token_id.to_string())?;
+    let mut sale_info = sales().load(deps.storage, key.clone())?;
+
+    if !accepts_denom(&sale_info, &denom) {
+        return Err(crate::ContractError::TokenTypeMismatch {});
+    }
+
+    //Bids in another accepted denom are placed once swapped into the denom of the auction
+    if sale_info.sale_type == SaleType::Auction && denom != sale_info.denom {
+        return swap_bid(deps, env, key, address, amount, denom, &sale_info);
+    }
+    let price = amount;
 
     match sale_info.duration_type.clone() {
         DurationType::Fixed => {
@@ -553,7 +1512,7 @@ pub fn handle_propose(
             }
         },
         DurationType::Bid(threshold) => {
-            if sale_info.requests.len() as u32 > threshold {
+            if sale_info.bid_count >= threshold {
                 return Err(crate::ContractError::AlreadyExpired{})
             }
         },
@@ -562,7 +1521,11 @@ pub fn handle_propose(
     let mut list = sale_info.requests.clone();
     
     if sale_info.sale_type == SaleType::Fixed {
-        if sale_info.initial_price > price {
+        if fixed_price(&sale_info, &denom) > amount {
+            return Err(crate::ContractError::LowerPrice{})
+        }
+    } else if sale_info.sale_type == SaleType::Dutch {
+        if current_price(&sale_info, env.block.time.seconds()) > price {
             return Err(crate::ContractError::LowerPrice{})
         }
     } else if sale_info.sale_type == SaleType::Auction {
@@ -570,96 +1533,390 @@ pub fn handle_propose(
         if list.len() == 0 && price < sale_info.initial_price || list.len() > 0 && list[list.len() - 1].price >= price {
             return Err(crate::ContractError::LowerThanPrevious {})
         }
+        if list.len() > 0 {
+            let previous = list[list.len() - 1].price;
+            if price < previous + bid_increment(&sale_info, previous) {
+                return Err(crate::ContractError::BidIncrementTooLow {})
+            }
+        }
+    }
+    //The outbid bidder can withdraw from the refund ledger
+    if sale_info.sale_type == SaleType::Auction {
+        for request in list.clone() {
+            BIDS.remove(deps.storage, (&request.address, &key));
+            util::credit_refund(deps.storage, request.address, request.denom, request.amount)?;
+        }
+        list = vec![];
     }
-    // let mut lastitem = Request {
-    //     address: address.clone(),
-    //     price: Uint128::zero()
-    // };
-    // if list.len() > 0 {
-    //     let len = list.len();
-    //     lastitem = list[len - 1].clone();
-    // }
-    list.push(Request {
+    let request = Request {
         address: address.clone(),
-        price
-    });
+        price,
+        denom: denom.clone(),
+        amount
+    };
+    if sale_info.sale_type == SaleType::Auction {
+        BIDS.save(deps.storage, (&address, &key), &request)?;
+    }
+    list.push(request);
     
     sale_info.requests = list.clone();
+    sale_info.bid_count += 1;
 
     if sale_info.sale_type == SaleType::Auction && price >= sale_info.reserve_price {
         sale_info.can_accept = true;
     }
 
-    SALE.save(deps.storage, token_id.to_string(), &sale_info)?;
+    //Push the end of the auction out when the bid lands in the anti-sniping window
+    let mut extended_end: Option<u64> = None;
+    if let (DurationType::Time(start, end), Some(anti_sniping)) = (sale_info.duration_type.clone(), sale_info.anti_sniping.clone()) {
+        let now = env.block.time.seconds();
+        if sale_info.sale_type == SaleType::Auction && end - now <= anti_sniping.window && now + anti_sniping.extension > end {
+            sale_info.duration_type = DurationType::Time(start, now + anti_sniping.extension);
+            extended_end = Some(now + anti_sniping.extension);
+        }
+    }
+
+    sales().save(deps.storage, key.clone(), &sale_info)?;
 
     //Handle Fixed
     if sale_info.sale_type == SaleType::Fixed {
         //send NFT messages
-        let msgs = sell_nft_messages(deps.storage, deps.api, address.clone(), price, sale_info)?;
+        let (msgs, platform_fee) = sell_nft_messages(deps.storage, deps.querier, address.clone(), amount, sale_token_ids(&sale_info), sale_info.provider.clone(), denom.clone())?;
         //Remove Entry
-        SALE.remove(deps.storage, token_id.to_string());
+        sales().remove(deps.storage, key.clone())?;
+        util::record_trade(deps.storage, env.block.time.seconds(), sale_token_ids(&sale_info), sale_info.provider.clone(), address.clone(), amount, denom.clone())?;
 
         return Ok(Response::new()
             .add_messages(msgs)
             .add_attribute("action", "fixed_sell")
             .add_attribute("address", address.clone())
-            .add_attribute("token_id", token_id.to_string())
-            .add_attribute("price", price)
+            .add_attribute("token_id", key.clone())
+            .add_attribute("price", amount)
+            .add_attribute("platform_fee", platform_fee)
         );
 
-    } else {
-        let mut msgs:Vec<CosmosMsg> = vec![];
-        // if list.len() > 1 {
-        //     msgs.push(util::transfer_token_message(sale_info.denom.clone(), lastitem.price, lastitem.address.clone())?);
-        // }
-        
-        Ok(Response::new()
+    } else if sale_info.sale_type == SaleType::Dutch {
+        //sell at the current price and return the excess
+        let sell_price = current_price(&sale_info, env.block.time.seconds());
+        let (mut msgs, platform_fee) = sell_nft_messages(deps.storage, deps.querier, address.clone(), sell_price, sale_token_ids(&sale_info), sale_info.provider.clone(), sale_info.denom.clone())?;
+        if price > sell_price {
+            msgs.push(util::transfer_token_message(sale_info.denom.clone(), price - sell_price, address.clone())?);
+        }
+        //Remove Entry
+        sales().remove(deps.storage, key.clone())?;
+        util::record_trade(deps.storage, env.block.time.seconds(), sale_token_ids(&sale_info), sale_info.provider.clone(), address.clone(), sell_price, sale_info.denom.clone())?;
+
+        return Ok(Response::new()
             .add_messages(msgs)
+            .add_attribute("action", "dutch_sell")
+            .add_attribute("address", address.clone())
+            .add_attribute("token_id", key.clone())
+            .add_attribute("price", sell_price)
+            .add_attribute("platform_fee", platform_fee)
+        );
+
+    } else {
+        let mut res = Response::new()
             .add_attribute("action", "propose")
             .add_attribute("address", address.clone())
-            .add_attribute("token_id", token_id.to_string())
-            .add_attribute("price", price)
-        )    
+            .add_attribute("token_id", key.clone())
+            .add_attribute("price", price);
+        if let Some(end) = extended_end {
+            res = res.add_attribute("end_time", end.to_string());
+        }
+        Ok(res)
+    }
+}
+
+fn bid_increment(
+    sale_info: &SaleInfo,
+    previous: Uint128
+) -> Uint128 {
+    match sale_info.min_increment.clone() {
+        Some(BidIncrement::Absolute(amount)) => amount,
+        Some(BidIncrement::BasisPoints(bps)) => previous * Uint128::from(bps) / Uint128::from(10000u32),
+        None => Uint128::zero()
+    }
+}
+
+// Lowest bid the auction takes next
+fn min_bid(
+    sale_info: &SaleInfo
+) -> Uint128 {
+    match sale_info.requests.last() {
+        Some(top) => top.price + bid_increment(sale_info, top.price).max(Uint128::new(1)),
+        None => sale_info.initial_price
+    }
+}
+
+// Swaps the bid into the denom of the auction, the reply places it with the swap output
+fn swap_bid(
+    deps: DepsMut,
+    env: Env,
+    key: String,
+    address: Addr,
+    amount: Uint128,
+    denom: Denom,
+    sale_info: &SaleInfo
+) -> Result<Response, crate::ContractError> {
+    let (pool_address, is_token1) = util::find_swap_pool(deps.storage, deps.querier, denom.clone(), sale_info.denom.clone())?;
+    let input_token = if is_token1 { TokenSelect::Token1 } else { TokenSelect::Token2 };
+
+    PENDING_SWAP.save(deps.storage, &PendingSwap {
+        key: key.clone(),
+        buyer: address.clone(),
+        denom: sale_info.denom.clone(),
+        balance_before: util::get_token_amount(deps.querier, sale_info.denom.clone(), env.contract.address.clone())?,
+        is_bid: true
+    })?;
+
+    let mut msgs = util::swap_token_messages(denom, input_token, amount, min_bid(sale_info), pool_address)?;
+    let swap_msg = msgs.pop().unwrap();
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_submessage(SubMsg {
+            msg: swap_msg,
+            id: SWAP_REPLY_ID,
+            gas_limit: None,
+            reply_on: ReplyOn::Success,
+        })
+        .add_attribute("action", "swap_bid")
+        .add_attribute("address", address)
+        .add_attribute("token_id", key)
+        .add_attribute("amount", amount)
+    )
+}
+
+pub fn execute_swap_propose(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_id: u32,
+    denom: String,
+    min_output: Uint128
+) -> Result<Response, crate::ContractError> {
+
+    let amount = util::get_amount_of_denom(Balance::from(info.funds), Denom::Native(denom.clone()))?;
+
+    handle_swap_propose(deps, env, token_id, info.sender.clone(), amount, Denom::Native(denom), min_output)
+}
+
+// Swaps the payment into the listing denom, the NFT is sold in the reply once the output is known
+pub fn handle_swap_propose(
+    deps: DepsMut,
+    env: Env,
+    token_id: u32,
+    address: Addr,
+    amount: Uint128,
+    denom: Denom,
+    min_output: Uint128
+) -> Result<Response, crate::ContractError> {
+
+    util::check_enabled(deps.storage, util::Activity::Bidding)?;
+    if sales().may_load(deps.storage, token_id.to_string())?.is_none() {
+        return Err(crate::ContractError::NotOnSale {});
+    }
+    let sale_info = sales().load(deps.storage, token_id.to_string())?;
+
+    if sale_info.sale_type != SaleType::Fixed {
+        return Err(crate::ContractError::InvalidSaleType {});
+    }
+    if sale_info.denom == denom {
+        return Err(crate::ContractError::InvalidBuyParam {});
+    }
+    if min_output < sale_info.initial_price {
+        return Err(crate::ContractError::LowerPrice {});
+    }
+
+    let (pool_address, is_token1) = util::find_swap_pool(deps.storage, deps.querier, denom.clone(), sale_info.denom.clone())?;
+    let input_token = if is_token1 { TokenSelect::Token1 } else { TokenSelect::Token2 };
+
+    PENDING_SWAP.save(deps.storage, &PendingSwap {
+        key: token_id.to_string(),
+        buyer: address.clone(),
+        denom: sale_info.denom.clone(),
+        balance_before: util::get_token_amount(deps.querier, sale_info.denom.clone(), env.contract.address.clone())?,
+        is_bid: false
+    })?;
+
+    let mut msgs = util::swap_token_messages(denom, input_token, amount, min_output, pool_address)?;
+    let swap_msg = msgs.pop().unwrap();
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_submessage(SubMsg {
+            msg: swap_msg,
+            id: SWAP_REPLY_ID,
+            gas_limit: None,
+            reply_on: ReplyOn::Success,
+        })
+        .add_attribute("action", "swap_propose")
+        .add_attribute("address", address)
+        .add_attribute("token_id", token_id.to_string())
+        .add_attribute("amount", amount)
+    )
+}
+
+fn reply_swap(
+    deps: DepsMut,
+    env: Env
+) -> Result<Response, crate::ContractError> {
+    let pending = PENDING_SWAP.load(deps.storage)?;
+    PENDING_SWAP.remove(deps.storage);
+
+    let balance = util::get_token_amount(deps.querier, pending.denom.clone(), env.contract.address.clone())?;
+    let received = balance - pending.balance_before;
+
+    if pending.is_bid {
+        return handle_propose(deps, env, pending.key, pending.buyer, received, pending.denom);
+    }
+
+    let sale_info = sales().load(deps.storage, pending.key.clone())?;
+    let price = sale_info.initial_price;
+    if received < price {
+        return Err(crate::ContractError::LowerPrice {});
+    }
+
+    let (mut msgs, platform_fee) = sell_nft_messages(deps.storage, deps.querier, pending.buyer.clone(), price, sale_token_ids(&sale_info), sale_info.provider.clone(), sale_info.denom.clone())?;
+    if received > price {
+        msgs.push(util::transfer_token_message(sale_info.denom.clone(), received - price, pending.buyer.clone())?);
     }
+    sales().remove(deps.storage, pending.key.clone())?;
+    util::record_trade(deps.storage, env.block.time.seconds(), sale_token_ids(&sale_info), sale_info.provider.clone(), pending.buyer.clone(), price, sale_info.denom.clone())?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "swap_sell")
+        .add_attribute("address", pending.buyer)
+        .add_attribute("token_id", pending.key)
+        .add_attribute("price", price)
+        .add_attribute("platform_fee", platform_fee)
+    )
 }
 
-const MULTIPLY:u32 = 1000000u32;
+pub const MULTIPLY:u32 = 1000000u32;
+
+pub fn accepts_denom(
+    sale_info: &SaleInfo,
+    denom: &Denom
+) -> bool {
+    sale_info.denom == *denom
+        || sale_info.prices.iter().any(|item| item.denom == *denom)
+        || sale_info.accepted_denoms.contains(denom)
+}
+
+// Price of a fixed sale in the given denom
+pub fn fixed_price(
+    sale_info: &SaleInfo,
+    denom: &Denom
+) -> Uint128 {
+    match sale_info.prices.iter().find(|item| item.denom == *denom) {
+        Some(item) => item.price,
+        None => sale_info.initial_price
+    }
+}
+
+// Dutch auctions fall linearly from initial_price to reserve_price over the Time window
+pub fn current_price(
+    sale_info: &SaleInfo,
+    now: u64
+) -> Uint128 {
+    if sale_info.sale_type != SaleType::Dutch {
+        return sale_info.initial_price;
+    }
+    match sale_info.duration_type.clone() {
+        DurationType::Time(start, end) => {
+            if now <= start {
+                return sale_info.initial_price;
+            }
+            if now >= end {
+                return sale_info.reserve_price;
+            }
+            let drop = (sale_info.initial_price - sale_info.reserve_price).multiply_ratio(now - start, end - start);
+            sale_info.initial_price - drop
+        },
+        _ => sale_info.initial_price
+    }
+}
 
 pub fn sell_nft_messages (
     storage: &mut dyn Storage,
-    api: &dyn Api,
+    querier: QuerierWrapper,
     recipient: Addr,
     amount: Uint128,
-    sale_info: SaleInfo
-) -> Result<Vec<CosmosMsg>, crate::ContractError> {
+    token_ids: Vec<u32>,
+    provider: Addr,
+    denom: Denom
+) -> Result<(Vec<CosmosMsg>, Uint128), crate::ContractError> {
     let cfg = CONFIG.load(storage)?;
-    let mut list:Vec<Request> = vec![];
+
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    for token_id in token_ids.clone() {
+        msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: cfg.cw721_address.clone().unwrap().to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw721ExecuteMsg::<Extension>::TransferNft {
+                recipient: recipient.clone().into(),
+                token_id: token_id.to_string()
+            })?,
+        }));
+    }
+
+    //Royalties set on the token take precedence over the collection ones, bundles pay the collection royalties once
+    let mut royalties = cfg.royalties;
+    if token_ids.len() == 1 {
+        let nft_info: NftInfoResponse<Extension> = querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: cfg.cw721_address.clone().unwrap().to_string(),
+            msg: to_binary(&Cw721QueryMsg::NftInfo {
+                token_id: token_ids[0].to_string()
+            })?,
+        }))?;
+        if let Some(token_royalties) = nft_info.extension.and_then(|metadata| metadata.royalties) {
+            royalties = token_royalties;
+        }
+    }
+
+    //The platform fee is taken before royalties
+    let mut platform_fee = Uint128::zero();
+    if let Some(fee) = cfg.platform_fee {
+        platform_fee = amount * Uint128::from(fee.rate) / Uint128::from(MULTIPLY);
+        if platform_fee > Uint128::zero() {
+            msgs.push(util::transfer_token_message(denom.clone(), platform_fee, fee.address)?);
+        }
+    }
+
+    let amount = amount.checked_sub(platform_fee).map_err(|_| crate::ContractError::InvalidPlatformFee {})?;
+    msgs.append(&mut royalty_messages(royalties, denom, amount, provider)?);
+
+    Ok((msgs, platform_fee))
+}
+
+// Pays every royalty its rate of amount and sends the rest to receiver
+pub fn royalty_messages (
+    royalties: Vec<Royalty>,
+    denom: Denom,
+    amount: Uint128,
+    receiver: Addr
+) -> Result<Vec<CosmosMsg>, crate::ContractError> {
+    let mut list:Vec<(Addr, Uint128)> = vec![];
 
     let mut provider_amount = amount;
 
-    for item in cfg.royalties {
+    for item in royalties {
         let amount = amount * Uint128::from(item.rate) / Uint128::from(MULTIPLY);
-        provider_amount -= amount;
-        list.push(Request { address: item.address.clone(), price: amount });
+        provider_amount = provider_amount.checked_sub(amount).map_err(|_| crate::ContractError::ExceedsMaximumRoyaltyFee {})?;
+        list.push((item.address.clone(), amount));
     }
     
-    list.push(Request { address: sale_info.provider.clone(), price: provider_amount });
+    list.push((receiver, provider_amount));
 
     let mut msgs: Vec<CosmosMsg> = vec![];
-    msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: cfg.cw721_address.clone().unwrap().to_string(),
-        funds: vec![],
-        msg: to_binary(&Cw721ExecuteMsg::<Extension>::TransferNft {
-            recipient: recipient.clone().into(),
-            token_id: sale_info.token_id.to_string()
-        })?,
-    }));
-
-    for item in list {
-        if item.price == Uint128::zero() {
+    for (address, amount) in list {
+        if amount == Uint128::zero() {
             continue;
         }
-        msgs.push(util::transfer_token_message(sale_info.denom.clone(), item.price, item.address.clone())?);
+        msgs.push(util::transfer_token_message(denom.clone(), amount, address)?);
     }
 
     Ok(msgs)
@@ -674,10 +1931,14 @@ pub fn execute_edit_sale(
     duration_type: DurationType,
     initial_price: Uint128,
     reserve_price: Uint128,
-    denom: Denom 
+    denom: Denom,
+    anti_sniping: Option<AntiSniping>,
+    min_increment: Option<BidIncrement>,
+    prices: Vec<DenomPrice>,
+    accepted_denoms: Vec<Denom>
 ) -> Result<Response, crate::ContractError> {
 
-    let mut sale_info = SALE.load(deps.storage, token_id.to_string())?;
+    let mut sale_info = sales().load(deps.storage, token_id.to_string())?;
     if sale_info.provider != info.sender.clone() {
         return Err(crate::ContractError::Unauthorized {  });
     }
@@ -690,48 +1951,265 @@ pub fn execute_edit_sale(
     sale_info.duration_type = duration_type;
     sale_info.initial_price = initial_price;
     sale_info.reserve_price = reserve_price;
+    sale_info.anti_sniping = anti_sniping;
+    sale_info.min_increment = min_increment;
+    sale_info.prices = prices;
+    sale_info.accepted_denoms = accepted_denoms;
+    check_sale_info(&sale_info)?;
 
-    SALE.save(deps.storage, token_id.to_string(), &sale_info)?;
+    sales().save(deps.storage, token_id.to_string(), &sale_info)?;
     Ok(Response::new()
         .add_attribute("action", "edit_sale")
         .add_attribute("token_id", token_id.to_string()))
 }
 
 
+// The top bid is binding once it meets the reserve or the auction is over
 pub fn execute_cancel_propose(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    token_id: u32,
+    key: String,
 ) -> Result<Response, crate::ContractError> {
 
-    let mut sale_info = SALE.load(deps.storage, token_id.to_string())?;
+    let mut sale_info = sales().load(deps.storage, key.clone())?;
     let list = sale_info.requests.clone();
     let mut new_list: Vec<Request> = vec![];
-    let mut cancel_price = Uint128::zero();
+    let mut cancelled: Option<Request> = None;
 
     for i in 0.. list.len() {
         if list[i].address == info.sender.clone() {
-            cancel_price = list[i].price;
+            cancelled = Some(list[i].clone());
             continue;
         }
         new_list.push(list[i].clone());
     }
+    let cancelled = cancelled.ok_or(crate::ContractError::NoBids {})?;
+
+    if sale_info.can_accept || is_ended(&sale_info, env.block.time.seconds()) {
+        return Err(crate::ContractError::CannotCancelBid {});
+    }
     
     sale_info.requests = new_list;
+    sale_info.can_accept = sale_info.sale_type == SaleType::Auction && sale_info.requests.iter().any(|request| request.price >= sale_info.reserve_price);
 
-    SALE.save(deps.storage, token_id.to_string(), &sale_info)?;
-
-    let mut msgs: Vec<CosmosMsg> = vec![];
-    msgs.push(util::transfer_token_message(sale_info.denom.clone(), cancel_price, info.sender.clone())?);
+    sales().save(deps.storage, key.clone(), &sale_info)?;
+    BIDS.remove(deps.storage, (&info.sender, &key));
+    util::credit_refund(deps.storage, info.sender.clone(), cancelled.denom, cancelled.amount)?;
 
     Ok(Response::new()
         .add_attribute("action", "cancel_propose")
-        .add_attribute("token_id", token_id.to_string())
+        .add_attribute("token_id", key.clone())
         .add_attribute("address", info.sender.clone().to_string())
+    )
+}
+
+// Whether the sale stopped taking bids and can only be settled
+fn is_ended(
+    sale_info: &SaleInfo,
+    now: u64
+) -> bool {
+    match sale_info.duration_type {
+        DurationType::Time(_start, end) => now > end,
+        DurationType::Bid(threshold) => sale_info.bid_count >= threshold,
+        DurationType::Fixed => false
+    }
+}
+
+pub fn execute_offer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_id: u32,
+    denom: String,
+    expires: Expiration
+) -> Result<Response, crate::ContractError> {
+
+    let amount = util::get_amount_of_denom(Balance::from(info.funds), Denom::Native(denom.clone()))?;
+
+    handle_offer(deps, env, token_id, info.sender.clone(), amount, Denom::Native(denom), expires)
+}
+
+// Escrows the funds of an offer on any minted token, listed or not
+pub fn handle_offer(
+    deps: DepsMut,
+    env: Env,
+    token_id: u32,
+    address: Addr,
+    price: Uint128,
+    denom: Denom,
+    expires: Expiration
+) -> Result<Response, crate::ContractError> {
+
+    util::check_enabled(deps.storage, util::Activity::Bidding)?;
+    let cfg = CONFIG.load(deps.storage)?;
+
+    if expires.is_expired(&env.block) {
+        return Err(crate::ContractError::AlreadyExpired {});
+    }
+
+    let _owner: OwnerOfResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: cfg.cw721_address.clone().ok_or(crate::ContractError::Uninitialized {})?.to_string(),
+        msg: to_binary(&Cw721QueryMsg::OwnerOf {
+            token_id: token_id.to_string(),
+            include_expired: None
+        })?,
+    })).map_err(|_| crate::ContractError::NotMinted {})?;
+
+    let key = token_id.to_string();
+    if OFFERS.has(deps.storage, (&key, &address)) {
+        return Err(crate::ContractError::AlreadyOffered {});
+    }
+
+    let offer = Offer {
+        token_id,
+        bidder: address.clone(),
+        price,
+        denom,
+        expires
+    };
+    OFFERS.save(deps.storage, (&key, &address), &offer)?;
+    OFFERS_BY_BIDDER.save(deps.storage, (&address, &key), &offer)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "offer")
+        .add_attribute("address", address)
+        .add_attribute("token_id", key)
+        .add_attribute("price", price)
+    )
+}
+
+pub fn execute_cancel_offer(
+    deps: DepsMut,
+    info: MessageInfo,
+    token_id: u32
+) -> Result<Response, crate::ContractError> {
+
+    let key = token_id.to_string();
+    let offer = OFFERS.may_load(deps.storage, (&key, &info.sender))?
+        .ok_or(crate::ContractError::OfferNotFound {})?;
+
+    OFFERS.remove(deps.storage, (&key, &info.sender));
+    OFFERS_BY_BIDDER.remove(deps.storage, (&info.sender, &key));
+
+    let msg = util::transfer_token_message(offer.denom.clone(), offer.price, info.sender.clone())?;
+
+    Ok(Response::new()
+        .add_message(msg)
+        .add_attribute("action", "cancel_offer")
+        .add_attribute("token_id", key)
+        .add_attribute("address", info.sender.to_string())
+    )
+}
+
+pub fn execute_collection_bid(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String
+) -> Result<Response, crate::ContractError> {
+
+    let amount = util::get_amount_of_denom(Balance::from(info.funds), Denom::Native(denom.clone()))?;
+
+    handle_collection_bid(deps, info.sender.clone(), amount, Denom::Native(denom))
+}
+
+// Escrows a bid that any holder can fill with any token of the collection
+pub fn handle_collection_bid(
+    deps: DepsMut,
+    address: Addr,
+    price: Uint128,
+    denom: Denom
+) -> Result<Response, crate::ContractError> {
+
+    util::check_enabled(deps.storage, util::Activity::Bidding)?;
+
+    if COLLECTION_BIDS.has(deps.storage, &address) {
+        return Err(crate::ContractError::AlreadyOffered {});
+    }
+
+    let bid = CollectionBid {
+        bidder: address.clone(),
+        price,
+        denom: denom.clone()
+    };
+    COLLECTION_BIDS.save(deps.storage, &address, &bid)?;
+    COLLECTION_BID_RANKING.save(deps.storage, (&util::denom_key(denom), &collection_bid_rank(&bid)), &address)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "collection_bid")
+        .add_attribute("address", address)
+        .add_attribute("price", price)
+    )
+}
+
+pub fn execute_cancel_collection_bid(
+    deps: DepsMut,
+    info: MessageInfo
+) -> Result<Response, crate::ContractError> {
+
+    let bid = COLLECTION_BIDS.may_load(deps.storage, &info.sender)?
+        .ok_or(crate::ContractError::OfferNotFound {})?;
+
+    remove_collection_bid(deps.storage, &bid);
+
+    let msg = util::transfer_token_message(bid.denom.clone(), bid.price, info.sender.clone())?;
+
+    Ok(Response::new()
+        .add_message(msg)
+        .add_attribute("action", "cancel_collection_bid")
+        .add_attribute("address", info.sender.to_string())
+    )
+}
+
+pub fn execute_withdraw_refunds(
+    deps: DepsMut,
+    info: MessageInfo
+) -> Result<Response, crate::ContractError> {
+
+    let refunds:StdResult<Vec<_>> = REFUNDS
+        .prefix(&info.sender)
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect();
+
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    for (denom_key, refund) in refunds? {
+        REFUNDS.remove(deps.storage, (&info.sender, &denom_key));
+        msgs.push(util::transfer_token_message(refund.denom, refund.amount, info.sender.clone())?);
+    }
+
+    if msgs.len() == 0 {
+        return Err(crate::ContractError::NoRefunds {});
+    }
+
+    Ok(Response::new()
         .add_messages(msgs)
+        .add_attribute("action", "withdraw_refunds")
+        .add_attribute("address", info.sender.to_string())
     )
 }
 
+fn remove_collection_bid(
+    storage: &mut dyn Storage,
+    bid: &CollectionBid
+) {
+    COLLECTION_BIDS.remove(storage, &bid.bidder);
+    COLLECTION_BID_RANKING.remove(storage, (&util::denom_key(bid.denom.clone()), &collection_bid_rank(bid)));
+}
+
+fn collection_bid_rank(
+    bid: &CollectionBid
+) -> String {
+    rank_key(bid.price, bid.bidder.as_str())
+}
+
+// Uint128 has at most 39 digits, padding keeps the byte order equal to the price order
+fn rank_key(
+    price: Uint128,
+    suffix: &str
+) -> String {
+    format!("{:0>39}{}", price.to_string(), suffix)
+}
+
 
 pub fn execute_change_contract(
     deps: DepsMut,
@@ -779,6 +2257,93 @@ pub fn execute_change_cw721_owner(
 }
 
 
+pub fn execute_edit(
+    deps: DepsMut,
+    info: MessageInfo,
+    token_id: u32,
+    uri: Option<String>,
+    extension: Extension
+) -> Result<Response, crate::ContractError> {
+    util::check_owner(deps.storage, info.sender.clone())?;
+    util::check_extension_royalties(deps.storage, &extension)?;
+    let config = CONFIG.load(deps.storage)?;
+    let cw721_address = config.cw721_address.clone().ok_or(crate::ContractError::Uninitialized {})?;
+
+    let edit_msg = Cw721ExecuteMsg::Edit(EditMsg::<Extension> {
+        token_id: token_id.to_string(),
+        token_uri: uri,
+        extension: extension.map(Some)
+    });
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: cw721_address.to_string(),
+            msg: to_binary(&edit_msg)?,
+            funds: vec![],
+        }))
+        .add_attribute("action", "edit")
+        .add_attribute("token_id", token_id.to_string())
+    )
+}
+
+pub fn execute_freeze_metadata(
+    deps: DepsMut,
+    info: MessageInfo,
+    token_id: Option<u32>
+) -> Result<Response, crate::ContractError> {
+    util::check_owner(deps.storage, info.sender.clone())?;
+    let config = CONFIG.load(deps.storage)?;
+    let cw721_address = config.cw721_address.clone().ok_or(crate::ContractError::Uninitialized {})?;
+
+    let freeze_msg = Cw721ExecuteMsg::<Extension>::FreezeMetadata {
+        token_id: token_id.map(|token_id| token_id.to_string())
+    };
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: cw721_address.to_string(),
+            msg: to_binary(&freeze_msg)?,
+            funds: vec![],
+        }))
+        .add_attribute("action", "freeze_metadata")
+        .add_attribute("token_id", token_id.map_or("all".to_string(), |token_id| token_id.to_string()))
+    )
+}
+
+pub fn execute_reveal(
+    deps: DepsMut,
+    info: MessageInfo,
+    base_uri: String
+) -> Result<Response, crate::ContractError> {
+    util::check_owner(deps.storage, info.sender.clone())?;
+    let mut config = CONFIG.load(deps.storage)?;
+    let cw721_address = config.cw721_address.clone().ok_or(crate::ContractError::Uninitialized {})?;
+
+    if config.placeholder_uri.is_none() {
+        return Err(crate::ContractError::NotSupported {});
+    }
+    if config.base_uri.is_some() {
+        return Err(crate::ContractError::AlreadyRevealed {});
+    }
+
+    config.base_uri = Some(base_uri.clone());
+    CONFIG.save(deps.storage, &config)?;
+
+    let reveal_msg = Cw721ExecuteMsg::<Extension>::Reveal {
+        base_uri: base_uri.clone()
+    };
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: cw721_address.to_string(),
+            msg: to_binary(&reveal_msg)?,
+            funds: vec![],
+        }))
+        .add_attribute("action", "reveal")
+        .add_attribute("base_uri", base_uri)
+    )
+}
+
 pub fn execute_update_unused_token_id(
     deps: DepsMut,
     info: MessageInfo,
@@ -786,6 +2351,10 @@ pub fn execute_update_unused_token_id(
 ) -> Result<Response, crate::ContractError> {
     util::check_owner(deps.storage, info.sender.clone())?;
     let mut config = CONFIG.load(deps.storage)?;
+    //The shuffled pool is sized from unused_token_id
+    if config.shuffle {
+        return Err(crate::ContractError::NotSupported {});
+    }
     config.unused_token_id = token_id;
     CONFIG.save(deps.storage, &config)?;
 
@@ -804,5 +2373,34 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, c
             previous_contract: version.contract,
         });
     }
+
+    //Rewrites the config in the current layout, fields added since the deployment take their defaults
+    let mut config = CONFIG.load(deps.storage)?;
+    if let Some(enabled) = LEGACY_ENABLED.load(deps.storage)?.enabled {
+        config.paused = PauseState {
+            minting: !enabled,
+            listing: !enabled,
+            bidding: !enabled,
+            settlement: !enabled
+        };
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    //Saving the sales again writes the index entries of sales listed before the index existed
+    let listed: StdResult<Vec<_>> = sales()
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect();
+    for (key, mut sale_info) in listed? {
+        //Bids stored without their held funds were paid in the sale denom at their price
+        for request in sale_info.requests.iter_mut() {
+            if request.amount.is_zero() {
+                request.denom = sale_info.denom.clone();
+                request.amount = request.price;
+            }
+        }
+        sales().save(deps.storage, key, &sale_info)?;
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     Ok(Response::default())
 }
@@ -0,0 +1,1622 @@
+#![cfg(test)]
+use cosmwasm_std::testing::{
+    mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR,
+};
+use cosmwasm_std::{
+    coins, from_binary, from_slice, to_binary, Addr, BankMsg, ContractResult, CosmosMsg, Deps,
+    DepsMut, OwnedDeps, Reply, Response, Storage, SubMsgResponse, SubMsgResult, SystemResult,
+    Uint128, WasmMsg, WasmQuery,
+};
+
+use cw20::Denom;
+use cw721::{Cw721ReceiveMsg, NftInfoResponse, OwnerOfResponse};
+use cw721_base::{
+    msg::ExecuteMsg as Cw721ExecuteMsg, msg::QueryMsg as Cw721QueryMsg, Extension, Metadata,
+};
+use cw_storage_plus::Map;
+use cw_utils::Expiration;
+use sha2::Digest;
+use wasmswap::msg::{
+    ExecuteMsg as WasmswapExecuteMsg, InfoResponse as WasmswapInfoResponse, TokenSelect,
+};
+
+use crate::contract::{current_price, execute, instantiate, migrate, query, reply};
+use crate::msg::{
+    AntiSniping, BestCollectionBidResponse, BidIncrement, BidsByBidderResponse,
+    CollectionBidsResponse, DurationType, ExecuteMsg, InstantiateMsg, MigrateMsg, NftReceiveMsg,
+    OffersResponse, PauseState, PendingRefund, PendingRefundsResponse, PlatformFee, PoolInfo,
+    PoolsResponse, QueryMsg, RemainingTokensResponse, Request, Royalty, SaleInfo, SaleType,
+    SalesResponse, Trade, TradesResponse, VolumesResponse, WhitelistMintedResponse, WhitelistProof,
+};
+use crate::state::{sales, CONFIG, SALE_KEY};
+use crate::ContractError;
+
+const OWNER: &str = "owner";
+const ARTIST: &str = "artist";
+const SELLER: &str = "seller";
+const CW721: &str = "cw721";
+const DENOM: &str = "ujuno";
+const POOL: &str = "pool";
+
+// Collection with 2% royalties to the owner and 3% to the artist
+fn instantiate_msg(mint_price: u128) -> InstantiateMsg {
+    InstantiateMsg {
+        owner: Addr::unchecked(OWNER),
+        max_tokens: 100,
+        name: "Marble".to_string(),
+        symbol: "MRB".to_string(),
+        token_code_id: 1,
+        maximum_royalty_fee: 100000,
+        royalties: vec![
+            Royalty {
+                address: Addr::unchecked(OWNER),
+                rate: 20000,
+            },
+            Royalty {
+                address: Addr::unchecked(ARTIST),
+                rate: 30000,
+            },
+        ],
+        uri: "ipfs://collection".to_string(),
+        mint_price: Uint128::from(mint_price),
+        mint_denom: Denom::Native(DENOM.to_string()),
+        platform_fee: None,
+        provenance_hash: None,
+        placeholder_uri: None,
+        shuffle: false,
+    }
+}
+
+// Collection of instantiate_msg linked to CW721
+fn setup_contract(deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, mint_price: u128) {
+    let msg = instantiate_msg(mint_price);
+    let res = instantiate(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+    assert_eq!(1, res.messages.len());
+
+    // stands in for the reply of the cw721 instantiation
+    let mut config = CONFIG.load(&deps.storage).unwrap();
+    config.cw721_address = Some(Addr::unchecked(CW721));
+    CONFIG.save(&mut deps.storage, &config).unwrap();
+
+    // tokens carry no royalties of their own
+    deps.querier.update_wasm(|_query| {
+        let info = NftInfoResponse::<Extension> {
+            token_uri: None,
+            extension: None,
+        };
+        SystemResult::Ok(ContractResult::Ok(to_binary(&info).unwrap()))
+    });
+}
+
+// Every token is owned by owner and carries no royalties
+fn mock_token_owner(deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, owner: &'static str) {
+    deps.querier.update_wasm(move |query| {
+        let res = match query {
+            WasmQuery::Smart { msg, .. } => match from_binary(msg).unwrap() {
+                Cw721QueryMsg::OwnerOf { .. } => to_binary(&OwnerOfResponse {
+                    owner: owner.to_string(),
+                    approvals: vec![],
+                }),
+                _ => to_binary(&NftInfoResponse::<Extension> {
+                    token_uri: None,
+                    extension: None,
+                }),
+            },
+            _ => panic!("unexpected query"),
+        };
+        SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+    });
+}
+
+// A wasmswap pool of uatom (token1) and DENOM at POOL, tokens carry no royalties
+fn mock_pool(deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>) {
+    deps.querier.update_wasm(|query| {
+        let res = match query {
+            WasmQuery::Smart { contract_addr, .. } if contract_addr == POOL => {
+                to_binary(&WasmswapInfoResponse {
+                    token1_reserve: Uint128::from(1000000u128),
+                    token1_denom: Denom::Native("uatom".to_string()),
+                    token2_reserve: Uint128::from(1000000u128),
+                    token2_denom: Denom::Native(DENOM.to_string()),
+                    lp_token_supply: Uint128::from(1000000u128),
+                    lp_token_address: "lp".to_string(),
+                })
+            }
+            _ => to_binary(&NftInfoResponse::<Extension> {
+                token_uri: None,
+                extension: None,
+            }),
+        };
+        SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+    });
+}
+
+fn bank_send(to_address: &str, amount: u128) -> CosmosMsg {
+    CosmosMsg::Bank(BankMsg::Send {
+        to_address: to_address.to_string(),
+        amount: coins(amount, DENOM),
+    })
+}
+
+fn transfer_nft(recipient: &str, token_id: &str) -> CosmosMsg {
+    CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: CW721.to_string(),
+        msg: to_binary(&Cw721ExecuteMsg::<Extension>::TransferNft {
+            recipient: recipient.to_string(),
+            token_id: token_id.to_string(),
+        })
+        .unwrap(),
+        funds: vec![],
+    })
+}
+
+// The cw721 contract forwarding token_id sent by sender
+fn send_nft(
+    deps: DepsMut,
+    sender: &str,
+    token_id: &str,
+    msg: NftReceiveMsg,
+) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+        sender: sender.to_string(),
+        token_id: token_id.to_string(),
+        msg: to_binary(&msg).unwrap(),
+    });
+    execute(deps, mock_env(), mock_info(CW721, &[]), msg)
+}
+
+// Lists token 1 of SELLER
+fn start_sale(
+    deps: DepsMut,
+    sale_type: SaleType,
+    duration_type: DurationType,
+    initial_price: u128,
+    reserve_price: u128,
+) {
+    let msg = NftReceiveMsg::StartSale {
+        sale_type,
+        duration_type,
+        initial_price: Uint128::from(initial_price),
+        reserve_price: Uint128::from(reserve_price),
+        denom: Denom::Native(DENOM.to_string()),
+        anti_sniping: None,
+        min_increment: None,
+        prices: vec![],
+        accepted_denoms: vec![],
+    };
+    send_nft(deps, SELLER, "1", msg).unwrap();
+}
+
+// Auction of token 1 running from 10 seconds ago to 100 seconds ahead
+fn start_auction(deps: DepsMut, reserve_price: u128) {
+    let now = mock_env().block.time.seconds();
+    start_sale(
+        deps,
+        SaleType::Auction,
+        DurationType::Time(now - 10, now + 100),
+        100,
+        reserve_price,
+    );
+}
+
+fn bid(deps: DepsMut, bidder: &str, amount: u128) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::Propose {
+        token_id: 1,
+        denom: DENOM.to_string(),
+    };
+    execute(
+        deps,
+        mock_env(),
+        mock_info(bidder, &coins(amount, DENOM)),
+        msg,
+    )
+}
+
+fn pending_refunds(deps: Deps, address: &str) -> Vec<PendingRefund> {
+    let msg = QueryMsg::PendingRefunds {
+        address: Addr::unchecked(address),
+    };
+    let res: PendingRefundsResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+    res.refunds
+}
+
+#[test]
+fn mint_payment() {
+    let mut deps = mock_dependencies();
+    setup_contract(&mut deps, 1000000);
+
+    let mint_msg = ExecuteMsg::Mint {
+        uri: "ipfs://1.json".to_string(),
+        extension: None,
+        whitelist: None,
+    };
+
+    // the mint price has to be paid exactly
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("minter", &coins(500000, DENOM)),
+        mint_msg.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::WrongPaymentAmount {}));
+
+    // and in the mint denom
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("minter", &coins(1000000, "uatom")),
+        mint_msg.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::NativeInputZero {}));
+
+    // the proceeds are split between the royalties and the owner
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("minter", &coins(1000000, DENOM)),
+        mint_msg,
+    )
+    .unwrap();
+    assert_eq!(4, res.messages.len());
+    assert_eq!(res.messages[1].msg, bank_send(OWNER, 20000));
+    assert_eq!(res.messages[2].msg, bank_send(ARTIST, 30000));
+    assert_eq!(res.messages[3].msg, bank_send(OWNER, 950000));
+
+    let config = CONFIG.load(&deps.storage).unwrap();
+    assert_eq!(config.unused_token_id, 2);
+}
+
+#[test]
+fn free_mint_rejects_funds() {
+    let mut deps = mock_dependencies();
+    setup_contract(&mut deps, 0);
+
+    let mint_msg = ExecuteMsg::Mint {
+        uri: "ipfs://1.json".to_string(),
+        extension: None,
+        whitelist: None,
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("minter", &coins(100, DENOM)),
+        mint_msg.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::IncorrectFunds {}));
+
+    // only the cw721 mint goes out
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("minter", &[]),
+        mint_msg,
+    )
+    .unwrap();
+    assert_eq!(1, res.messages.len());
+}
+
+#[test]
+fn outbid_refunds() {
+    let mut deps = mock_dependencies();
+    setup_contract(&mut deps, 0);
+    start_auction(deps.as_mut(), 1000);
+
+    bid(deps.as_mut(), "alice", 200).unwrap();
+
+    // bids have to beat the top bid
+    let err = bid(deps.as_mut(), "bob", 200).unwrap_err();
+    assert!(matches!(err, ContractError::LowerThanPrevious {}));
+
+    // the outbid bidder is credited instead of paid out right away
+    let res = bid(deps.as_mut(), "bob", 300).unwrap();
+    assert_eq!(0, res.messages.len());
+    assert_eq!(
+        pending_refunds(deps.as_ref(), "alice"),
+        vec![PendingRefund {
+            denom: Denom::Native(DENOM.to_string()),
+            amount: Uint128::from(200u128),
+        }]
+    );
+
+    // alice no longer has a bid to cancel
+    let msg = ExecuteMsg::CancelPropose { token_id: 1 };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::NoBids {}));
+
+    // the top bid below the reserve can be cancelled into the refunds
+    let res = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap();
+    assert_eq!(0, res.messages.len());
+    assert_eq!(
+        pending_refunds(deps.as_ref(), "bob")[0].amount,
+        Uint128::from(300u128)
+    );
+
+    // refunds are withdrawn once
+    let withdraw_msg = ExecuteMsg::WithdrawRefunds {};
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        withdraw_msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(res.messages[0].msg, bank_send("alice", 200));
+    assert_eq!(pending_refunds(deps.as_ref(), "alice"), vec![]);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        withdraw_msg,
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::NoRefunds {}));
+}
+
+#[test]
+fn binding_bid_cannot_be_cancelled() {
+    let mut deps = mock_dependencies();
+    setup_contract(&mut deps, 0);
+    start_auction(deps.as_mut(), 1000);
+
+    bid(deps.as_mut(), "alice", 1000).unwrap();
+
+    let msg = ExecuteMsg::CancelPropose { token_id: 1 };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::CannotCancelBid {}));
+}
+
+#[test]
+fn settle_auction_reserve_met() {
+    let mut deps = mock_dependencies();
+    setup_contract(&mut deps, 0);
+    start_auction(deps.as_mut(), 1000);
+
+    bid(deps.as_mut(), "alice", 500).unwrap();
+    bid(deps.as_mut(), "bob", 1000).unwrap();
+
+    // the auction has to be over
+    let settle_msg = ExecuteMsg::SettleSale { token_id: 1 };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        settle_msg.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::NotExpired {}));
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(200);
+    let res = execute(deps.as_mut(), env, mock_info("anyone", &[]), settle_msg).unwrap();
+
+    // the token goes to the top bidder, the seller gets the bid less royalties
+    assert_eq!(4, res.messages.len());
+    assert_eq!(res.messages[0].msg, transfer_nft("bob", "1"));
+    assert_eq!(res.messages[1].msg, bank_send(OWNER, 20));
+    assert_eq!(res.messages[2].msg, bank_send(ARTIST, 30));
+    assert_eq!(res.messages[3].msg, bank_send(SELLER, 950));
+
+    assert_eq!(
+        sales().may_load(&deps.storage, "1".to_string()).unwrap(),
+        None
+    );
+    assert_eq!(
+        pending_refunds(deps.as_ref(), "alice")[0].amount,
+        Uint128::from(500u128)
+    );
+    assert_eq!(pending_refunds(deps.as_ref(), "bob"), vec![]);
+}
+
+#[test]
+fn settle_auction_reserve_not_met() {
+    let mut deps = mock_dependencies();
+    setup_contract(&mut deps, 0);
+    start_auction(deps.as_mut(), 1000);
+
+    bid(deps.as_mut(), "alice", 500).unwrap();
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(200);
+    let settle_msg = ExecuteMsg::SettleSale { token_id: 1 };
+    let res = execute(deps.as_mut(), env, mock_info("anyone", &[]), settle_msg).unwrap();
+
+    // the token goes back to the seller and the bid to the refunds
+    assert_eq!(1, res.messages.len());
+    assert_eq!(res.messages[0].msg, transfer_nft(SELLER, "1"));
+    assert_eq!(res.events[0].ty, "reserve_not_met");
+
+    assert_eq!(
+        sales().may_load(&deps.storage, "1".to_string()).unwrap(),
+        None
+    );
+    assert_eq!(
+        pending_refunds(deps.as_ref(), "alice")[0].amount,
+        Uint128::from(500u128)
+    );
+}
+
+#[test]
+fn legacy_request_defaults() {
+    let request: Request = from_slice(br#"{"address":"alice","price":"200"}"#).unwrap();
+    assert_eq!(request.price, Uint128::from(200u128));
+    assert_eq!(request.amount, Uint128::zero());
+}
+
+#[test]
+fn migrate_legacy_bids() {
+    let mut deps = mock_dependencies();
+    setup_contract(&mut deps, 0);
+
+    // auction stored before bids recorded the funds they hold
+    let legacy_sale = br#"{"token_id":1,"provider":"seller","sale_type":"Auction","duration_type":{"Time":[1,2]},"initial_price":"100","reserve_price":"1000","requests":[{"address":"alice","price":"200"}],"denom":{"native":"ujuno"},"can_accept":false}"#;
+    let sale_key = Map::<&str, SaleInfo>::new(SALE_KEY).key("1");
+    deps.storage.set(&sale_key, legacy_sale);
+
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+    let sale_info = sales().load(&deps.storage, "1".to_string()).unwrap();
+    assert_eq!(
+        sale_info.requests,
+        vec![Request {
+            address: Addr::unchecked("alice"),
+            price: Uint128::from(200u128),
+            denom: Denom::Native(DENOM.to_string()),
+            amount: Uint128::from(200u128),
+        }]
+    );
+
+    // the migrated bid is refunded in full when the auction unwinds
+    let settle_msg = ExecuteMsg::SettleSale { token_id: 1 };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        settle_msg,
+    )
+    .unwrap();
+    assert_eq!(
+        pending_refunds(deps.as_ref(), "alice")[0].amount,
+        Uint128::from(200u128)
+    );
+}
+
+#[test]
+fn bid_count_auction_closes_at_threshold() {
+    let mut deps = mock_dependencies();
+    setup_contract(&mut deps, 0);
+    start_sale(
+        deps.as_mut(),
+        SaleType::Auction,
+        DurationType::Bid(2),
+        100,
+        100,
+    );
+
+    bid(deps.as_mut(), "alice", 200).unwrap();
+    bid(deps.as_mut(), "bob", 300).unwrap();
+
+    // the second bid made the auction settleable, a third one can not replace the winner
+    let err = bid(deps.as_mut(), "carol", 400).unwrap_err();
+    assert!(matches!(err, ContractError::AlreadyExpired {}));
+
+    let settle_msg = ExecuteMsg::SettleSale { token_id: 1 };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        settle_msg,
+    )
+    .unwrap();
+    assert_eq!(res.messages[0].msg, transfer_nft("bob", "1"));
+}
+
+#[test]
+fn dutch_price_decreases_linearly() {
+    let mut deps = mock_dependencies();
+    setup_contract(&mut deps, 0);
+    let now = mock_env().block.time.seconds();
+    start_sale(
+        deps.as_mut(),
+        SaleType::Dutch,
+        DurationType::Time(now, now + 100),
+        1000,
+        200,
+    );
+    let sale_info = sales().load(&deps.storage, "1".to_string()).unwrap();
+
+    // from the initial price at the start down to the reserve price at the end
+    assert_eq!(current_price(&sale_info, now), Uint128::from(1000u128));
+    assert_eq!(current_price(&sale_info, now + 50), Uint128::from(600u128));
+    assert_eq!(current_price(&sale_info, now + 100), Uint128::from(200u128));
+    assert_eq!(current_price(&sale_info, now + 500), Uint128::from(200u128));
+
+    // prices close to the Uint128 limit do not overflow
+    let mut large = sale_info.clone();
+    large.initial_price = Uint128::MAX;
+    large.reserve_price = Uint128::zero();
+    assert_eq!(
+        current_price(&large, now + 50),
+        Uint128::MAX - Uint128::MAX.multiply_ratio(1u128, 2u128)
+    );
+
+    // a purchase pays the current price and gets the excess back
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(50);
+    let msg = ExecuteMsg::Propose {
+        token_id: 1,
+        denom: DENOM.to_string(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("alice", &coins(1000, DENOM)),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(res.messages[0].msg, transfer_nft("alice", "1"));
+    assert_eq!(res.messages[3].msg, bank_send(SELLER, 570));
+    assert_eq!(res.messages[4].msg, bank_send("alice", 400));
+}
+
+#[test]
+fn bundle_sale_records_last_sale_per_token() {
+    let mut deps = mock_dependencies();
+    setup_contract(&mut deps, 0);
+    mock_token_owner(&mut deps, SELLER);
+
+    let msg = ExecuteMsg::StartBundleSale {
+        token_ids: vec![2, 3],
+        sale_type: SaleType::Fixed,
+        duration_type: DurationType::Fixed,
+        initial_price: Uint128::from(1000u128),
+        reserve_price: Uint128::from(1000u128),
+        denom: Denom::Native(DENOM.to_string()),
+        anti_sniping: None,
+        min_increment: None,
+        prices: vec![],
+        accepted_denoms: vec![],
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(SELLER, &[]), msg).unwrap();
+
+    let msg = ExecuteMsg::ProposeBundle {
+        bundle_id: 1,
+        denom: DENOM.to_string(),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(1000, DENOM)),
+        msg,
+    )
+    .unwrap();
+
+    // the bundle trade is the last sale of each of its tokens
+    for token_id in [2, 3] {
+        let msg = QueryMsg::GetLastSale { token_id };
+        let trade: Option<Trade> =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        let trade = trade.unwrap();
+        assert_eq!(trade.token_ids, vec![2, 3]);
+        assert_eq!(trade.buyer, Addr::unchecked("alice"));
+        assert_eq!(trade.price, Uint128::from(1000u128));
+    }
+    let msg = QueryMsg::GetLastSale { token_id: 1 };
+    let trade: Option<Trade> =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(trade, None);
+
+    // trades, newest first, and volumes count the bundle once
+    start_sale(
+        deps.as_mut(),
+        SaleType::Fixed,
+        DurationType::Fixed,
+        500,
+        500,
+    );
+    bid(deps.as_mut(), "bob", 500).unwrap();
+    let msg = QueryMsg::GetTrades {
+        start_after: None,
+        limit: None,
+    };
+    let res: TradesResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    let token_ids: Vec<_> = res.list.into_iter().map(|trade| trade.token_ids).collect();
+    assert_eq!(token_ids, vec![vec![1], vec![2, 3]]);
+    let msg = QueryMsg::GetVolumes {
+        start_after: None,
+        limit: None,
+    };
+    let res: VolumesResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.trade_count, 2);
+    assert_eq!(res.list[0].amount, Uint128::from(1500u128));
+    assert_eq!(res.list[0].trade_count, 2);
+}
+
+#[test]
+fn owner_transfer() {
+    let mut deps = mock_dependencies();
+    setup_contract(&mut deps, 0);
+
+    // a mistyped address is rejected instead of locking the transfer
+    let msg = ExecuteMsg::UpdateOwner {
+        owner: Addr::unchecked("NewOwner"),
+        expires: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::Std(_)));
+
+    let msg = ExecuteMsg::UpdateOwner {
+        owner: Addr::unchecked("newowner"),
+        expires: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+    assert_eq!(res.attributes[0].value, "propose_owner");
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("newowner", &[]),
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap();
+    assert_eq!(res.attributes[0].value, "accept_owner");
+    assert_eq!(
+        CONFIG.load(&deps.storage).unwrap().owner,
+        Addr::unchecked("newowner")
+    );
+
+    // the previous owner lost its rights
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::CancelOwnershipTransfer {},
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+}
+
+// Leaf hash committing address and mint allowance
+fn whitelist_leaf(address: &str, amount: u32) -> Vec<u8> {
+    sha2::Sha256::digest(format!("{}{}", address, amount).as_bytes()).to_vec()
+}
+
+fn whitelist_mint(
+    deps: DepsMut,
+    minter: &str,
+    amount: u32,
+    proof: &[u8],
+) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::Mint {
+        uri: "ipfs://1.json".to_string(),
+        extension: None,
+        whitelist: Some(WhitelistProof {
+            amount,
+            proof: vec![hex::encode(proof)],
+        }),
+    };
+    execute(deps, mock_env(), mock_info(minter, &[]), msg)
+}
+
+#[test]
+fn whitelist_mint_checks_proof_and_allowance() {
+    let mut deps = mock_dependencies();
+    setup_contract(&mut deps, 0);
+
+    // two leaf tree, alice may mint 2 and bob 1
+    let alice = whitelist_leaf("alice", 2);
+    let bob = whitelist_leaf("bob", 1);
+    let mut leaves = [alice.clone(), bob.clone()];
+    leaves.sort_unstable();
+    let root = sha2::Sha256::digest(&leaves.concat());
+    let msg = ExecuteMsg::RegisterMerkleRoot {
+        merkle_root: Some(hex::encode(root)),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+
+    // minting needs a proof once the root is set
+    let msg = ExecuteMsg::Mint {
+        uri: "ipfs://1.json".to_string(),
+        extension: None,
+        whitelist: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::WhitelistRequired {}));
+
+    // a proof for another allowance or another address does not verify
+    let err = whitelist_mint(deps.as_mut(), "alice", 3, &bob).unwrap_err();
+    assert!(matches!(err, ContractError::VerificationFailed {}));
+    let err = whitelist_mint(deps.as_mut(), "carol", 2, &bob).unwrap_err();
+    assert!(matches!(err, ContractError::VerificationFailed {}));
+
+    // alice mints up to her allowance
+    whitelist_mint(deps.as_mut(), "alice", 2, &bob).unwrap();
+    whitelist_mint(deps.as_mut(), "alice", 2, &bob).unwrap();
+    let err = whitelist_mint(deps.as_mut(), "alice", 2, &bob).unwrap_err();
+    assert!(matches!(err, ContractError::MintAllowanceExceeded {}));
+
+    let msg = QueryMsg::GetWhitelistMinted {
+        address: Addr::unchecked("alice"),
+    };
+    let res: WhitelistMintedResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.minted, 2);
+
+    // the allowance is per address
+    whitelist_mint(deps.as_mut(), "bob", 1, &alice).unwrap();
+    assert_eq!(CONFIG.load(&deps.storage).unwrap().unused_token_id, 4);
+}
+
+#[test]
+fn late_bid_extends_auction() {
+    let mut deps = mock_dependencies();
+    setup_contract(&mut deps, 0);
+    let now = mock_env().block.time.seconds();
+    let msg = NftReceiveMsg::StartSale {
+        sale_type: SaleType::Auction,
+        duration_type: DurationType::Time(now - 10, now + 100),
+        initial_price: Uint128::from(100u128),
+        reserve_price: Uint128::from(100u128),
+        denom: Denom::Native(DENOM.to_string()),
+        anti_sniping: Some(AntiSniping {
+            window: 60,
+            extension: 120,
+        }),
+        min_increment: None,
+        prices: vec![],
+        accepted_denoms: vec![],
+    };
+    send_nft(deps.as_mut(), SELLER, "1", msg).unwrap();
+
+    // a bid before the window leaves the end alone
+    let res = bid(deps.as_mut(), "alice", 200).unwrap();
+    assert!(res.attributes.iter().all(|attr| attr.key != "end_time"));
+
+    // a bid inside the window pushes the end out by the extension
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(50);
+    let msg = ExecuteMsg::Propose {
+        token_id: 1,
+        denom: DENOM.to_string(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("bob", &coins(300, DENOM)),
+        msg,
+    )
+    .unwrap();
+    let end_time = res
+        .attributes
+        .iter()
+        .find(|attr| attr.key == "end_time")
+        .unwrap();
+    assert_eq!(end_time.value, (now + 170).to_string());
+
+    let sale_info = sales().load(&deps.storage, "1".to_string()).unwrap();
+    assert_eq!(
+        sale_info.duration_type,
+        DurationType::Time(now - 10, now + 170)
+    );
+
+    // the auction can not be settled at its original end
+    env.block.time = env.block.time.plus_seconds(60);
+    let settle_msg = ExecuteMsg::SettleSale { token_id: 1 };
+    execute(deps.as_mut(), env, mock_info("anyone", &[]), settle_msg).unwrap_err();
+}
+
+#[test]
+fn auction_bids_respect_min_increment() {
+    let mut deps = mock_dependencies();
+    setup_contract(&mut deps, 0);
+    let now = mock_env().block.time.seconds();
+    let msg = NftReceiveMsg::StartSale {
+        sale_type: SaleType::Auction,
+        duration_type: DurationType::Time(now - 10, now + 100),
+        initial_price: Uint128::from(100u128),
+        reserve_price: Uint128::from(100u128),
+        denom: Denom::Native(DENOM.to_string()),
+        anti_sniping: None,
+        min_increment: Some(BidIncrement::Absolute(Uint128::from(50u128))),
+        prices: vec![],
+        accepted_denoms: vec![],
+    };
+    send_nft(deps.as_mut(), SELLER, "1", msg).unwrap();
+
+    let msg = QueryMsg::GetSale { token_id: 1 };
+    let sale_info: SaleInfo = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(
+        sale_info.min_increment,
+        Some(BidIncrement::Absolute(Uint128::from(50u128)))
+    );
+
+    bid(deps.as_mut(), "alice", 200).unwrap();
+    let err = bid(deps.as_mut(), "bob", 249).unwrap_err();
+    assert!(matches!(err, ContractError::BidIncrementTooLow {}));
+    bid(deps.as_mut(), "bob", 250).unwrap();
+
+    // 10% of the previous bid
+    let mut deps = mock_dependencies();
+    setup_contract(&mut deps, 0);
+    start_auction(deps.as_mut(), 100);
+    let msg = ExecuteMsg::EditSale {
+        token_id: 1,
+        sale_type: SaleType::Auction,
+        duration_type: DurationType::Time(now - 10, now + 100),
+        initial_price: Uint128::from(100u128),
+        reserve_price: Uint128::from(100u128),
+        denom: Denom::Native(DENOM.to_string()),
+        anti_sniping: None,
+        min_increment: Some(BidIncrement::BasisPoints(1000)),
+        prices: vec![],
+        accepted_denoms: vec![],
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(SELLER, &[]), msg).unwrap();
+
+    bid(deps.as_mut(), "alice", 200).unwrap();
+    let err = bid(deps.as_mut(), "bob", 219).unwrap_err();
+    assert!(matches!(err, ContractError::BidIncrementTooLow {}));
+    bid(deps.as_mut(), "bob", 220).unwrap();
+}
+
+#[test]
+fn offers_on_unlisted_tokens() {
+    let mut deps = mock_dependencies();
+    setup_contract(&mut deps, 0);
+    mock_token_owner(&mut deps, SELLER);
+
+    let expires = Expiration::AtHeight(mock_env().block.height + 100);
+    for (bidder, price) in [("alice", 500), ("bob", 400)] {
+        let msg = ExecuteMsg::Offer {
+            token_id: 5,
+            denom: DENOM.to_string(),
+            expires,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(bidder, &coins(price, DENOM)),
+            msg,
+        )
+        .unwrap();
+    }
+
+    let msg = QueryMsg::GetOffers {
+        token_id: 5,
+        start_after: None,
+        limit: None,
+    };
+    let res: OffersResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.list.len(), 2);
+    let msg = QueryMsg::GetOffersByBidder {
+        bidder: Addr::unchecked("bob"),
+        start_after: None,
+        limit: None,
+    };
+    let res: OffersResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.list[0].price, Uint128::from(400u128));
+
+    // the holder accepts by sending the token, the price goes through the royalty split
+    let msg = NftReceiveMsg::AcceptOffer {
+        bidder: "alice".to_string(),
+    };
+    let res = send_nft(deps.as_mut(), SELLER, "5", msg).unwrap();
+    assert_eq!(res.messages[0].msg, transfer_nft("alice", "5"));
+    assert_eq!(res.messages[1].msg, bank_send(OWNER, 10));
+    assert_eq!(res.messages[2].msg, bank_send(ARTIST, 15));
+    assert_eq!(res.messages[3].msg, bank_send(SELLER, 475));
+
+    // the bidder withdraws an offer that was not taken
+    let msg = ExecuteMsg::CancelOffer { token_id: 5 };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(res.messages[0].msg, bank_send("bob", 400));
+    let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::OfferNotFound {}));
+
+    // an expired offer can not be accepted
+    let msg = ExecuteMsg::Offer {
+        token_id: 5,
+        denom: DENOM.to_string(),
+        expires,
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("carol", &coins(300, DENOM)),
+        msg,
+    )
+    .unwrap();
+    let mut env = mock_env();
+    env.block.height += 100;
+    let msg = ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+        sender: "alice".to_string(),
+        token_id: "5".to_string(),
+        msg: to_binary(&NftReceiveMsg::AcceptOffer {
+            bidder: "carol".to_string(),
+        })
+        .unwrap(),
+    });
+    let err = execute(deps.as_mut(), env, mock_info(CW721, &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::AlreadyExpired {}));
+}
+
+#[test]
+fn collection_bids_ranked_by_price() {
+    let mut deps = mock_dependencies();
+    setup_contract(&mut deps, 0);
+
+    for (bidder, price) in [("alice", 300), ("bob", 1000), ("carol", 500)] {
+        let msg = ExecuteMsg::CollectionBid {
+            denom: DENOM.to_string(),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(bidder, &coins(price, DENOM)),
+            msg,
+        )
+        .unwrap();
+    }
+
+    // highest first, the padded rank keeps 1000 above 500
+    let msg = QueryMsg::GetCollectionBids {
+        denom: Denom::Native(DENOM.to_string()),
+        start_after: None,
+        limit: None,
+    };
+    let res: CollectionBidsResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    let bidders: Vec<_> = res.list.iter().map(|bid| bid.bidder.as_str()).collect();
+    assert_eq!(bidders, vec!["bob", "carol", "alice"]);
+
+    // any holder fills the best bid with any token
+    let msg = NftReceiveMsg::AcceptCollectionBid {
+        bidder: "bob".to_string(),
+    };
+    let res = send_nft(deps.as_mut(), SELLER, "7", msg).unwrap();
+    assert_eq!(res.messages[0].msg, transfer_nft("bob", "7"));
+    assert_eq!(res.messages[3].msg, bank_send(SELLER, 950));
+
+    let msg = QueryMsg::GetBestCollectionBid {
+        denom: Denom::Native(DENOM.to_string()),
+    };
+    let res: BestCollectionBidResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.bid.unwrap().bidder, Addr::unchecked("carol"));
+
+    let msg = ExecuteMsg::CancelCollectionBid {};
+    let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+    assert_eq!(res.messages[0].msg, bank_send("alice", 300));
+}
+
+// Metadata carrying its own royalties
+fn royalty_extension(royalties: Vec<(&str, u32)>) -> Extension {
+    Some(Metadata {
+        royalties: Some(
+            royalties
+                .into_iter()
+                .map(|(address, rate)| Royalty {
+                    address: Addr::unchecked(address),
+                    rate,
+                })
+                .collect(),
+        ),
+        ..Metadata::default()
+    })
+}
+
+#[test]
+fn token_royalties_override_collection_royalties() {
+    let mut deps = mock_dependencies();
+    setup_contract(&mut deps, 0);
+
+    // token royalties are capped like the collection ones, at mint and at edit
+    let msg = ExecuteMsg::Mint {
+        uri: "ipfs://1.json".to_string(),
+        extension: royalty_extension(vec![("creator", 60000), ("artist", 60000)]),
+        whitelist: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("minter", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::ExceedsMaximumRoyaltyFee {}));
+    let msg = ExecuteMsg::Edit {
+        token_id: 1,
+        uri: None,
+        extension: royalty_extension(vec![("creator", 100001)]),
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::ExceedsMaximumRoyaltyFee {}));
+
+    // a token with royalties of its own pays them instead of the collection ones
+    deps.querier.update_wasm(|_query| {
+        let info = NftInfoResponse::<Extension> {
+            token_uri: None,
+            extension: royalty_extension(vec![("creator", 100000)]),
+        };
+        SystemResult::Ok(ContractResult::Ok(to_binary(&info).unwrap()))
+    });
+    start_sale(
+        deps.as_mut(),
+        SaleType::Fixed,
+        DurationType::Fixed,
+        1000,
+        1000,
+    );
+    let res = bid(deps.as_mut(), "alice", 1000).unwrap();
+    assert_eq!(res.messages.len(), 3);
+    assert_eq!(res.messages[1].msg, bank_send("creator", 100));
+    assert_eq!(res.messages[2].msg, bank_send(SELLER, 900));
+}
+
+#[test]
+fn platform_fee_taken_before_royalties() {
+    // the fee and the royalties together can not exceed the sale price
+    let mut deps = mock_dependencies();
+    let mut msg = instantiate_msg(0);
+    msg.platform_fee = Some(PlatformFee {
+        address: Addr::unchecked("platform"),
+        rate: 900001,
+    });
+    let err = instantiate(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidPlatformFee {}));
+
+    let mut deps = mock_dependencies();
+    setup_contract(&mut deps, 0);
+    let mut config = CONFIG.load(&deps.storage).unwrap();
+    config.platform_fee = Some(PlatformFee {
+        address: Addr::unchecked("platform"),
+        rate: 100000,
+    });
+    CONFIG.save(&mut deps.storage, &config).unwrap();
+
+    // 10% of 1000 to the platform, the royalties are paid on the remaining 900
+    start_sale(
+        deps.as_mut(),
+        SaleType::Fixed,
+        DurationType::Fixed,
+        1000,
+        1000,
+    );
+    let res = bid(deps.as_mut(), "alice", 1000).unwrap();
+    assert_eq!(res.messages[1].msg, bank_send("platform", 100));
+    assert_eq!(res.messages[2].msg, bank_send(OWNER, 18));
+    assert_eq!(res.messages[3].msg, bank_send(ARTIST, 27));
+    assert_eq!(res.messages[4].msg, bank_send(SELLER, 855));
+    let fee = res
+        .attributes
+        .iter()
+        .find(|attr| attr.key == "platform_fee")
+        .unwrap();
+    assert_eq!(fee.value, "100");
+}
+
+#[test]
+fn fixed_sale_paid_through_swap() {
+    let mut deps = mock_dependencies();
+    setup_contract(&mut deps, 0);
+    mock_pool(&mut deps);
+    let msg = ExecuteMsg::AddPool {
+        denom: Denom::Native("uatom".to_string()),
+        pool_address: Addr::unchecked(POOL),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+    start_sale(
+        deps.as_mut(),
+        SaleType::Fixed,
+        DurationType::Fixed,
+        1000,
+        1000,
+    );
+
+    // the slippage bound has to cover the price
+    let msg = ExecuteMsg::SwapPropose {
+        token_id: 1,
+        denom: "uatom".to_string(),
+        min_output: Uint128::from(999u128),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(500, "uatom")),
+        msg,
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::LowerPrice {}));
+
+    // the payment is swapped first, nothing is sold yet
+    let msg = ExecuteMsg::SwapPropose {
+        token_id: 1,
+        denom: "uatom".to_string(),
+        min_output: Uint128::from(1000u128),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(500, "uatom")),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 1);
+    let swap = &res.messages[0];
+    assert_eq!(
+        swap.msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: POOL.to_string(),
+            msg: to_binary(&WasmswapExecuteMsg::Swap {
+                input_token: TokenSelect::Token1,
+                input_amount: Uint128::from(500u128),
+                min_output: Uint128::from(1000u128),
+                expiration: None,
+            })
+            .unwrap(),
+            funds: coins(500, "uatom"),
+        })
+    );
+    assert!(sales()
+        .may_load(&deps.storage, "1".to_string())
+        .unwrap()
+        .is_some());
+
+    // the reply sells at the listing price and returns the excess output
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, coins(1100, DENOM));
+    let msg = Reply {
+        id: swap.id,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: None,
+        }),
+    };
+    let res = reply(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(res.messages[0].msg, transfer_nft("alice", "1"));
+    assert_eq!(res.messages[3].msg, bank_send(SELLER, 950));
+    assert_eq!(res.messages[4].msg, bank_send("alice", 100));
+    assert!(sales()
+        .may_load(&deps.storage, "1".to_string())
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn pool_registry() {
+    let mut deps = mock_dependencies();
+    setup_contract(&mut deps, 0);
+    mock_pool(&mut deps);
+
+    let msg = ExecuteMsg::AddPool {
+        denom: Denom::Native("uatom".to_string()),
+        pool_address: Addr::unchecked(POOL),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+
+    // the pool has to hold the denom
+    let msg = ExecuteMsg::AddPool {
+        denom: Denom::Native("uosmo".to_string()),
+        pool_address: Addr::unchecked(POOL),
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::PoolAndTokenMismatch {}));
+
+    let msg = QueryMsg::GetPools {
+        start_after: None,
+        limit: None,
+    };
+    let res: PoolsResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg.clone()).unwrap()).unwrap();
+    assert_eq!(
+        res.list,
+        vec![PoolInfo {
+            denom: Denom::Native("uatom".to_string()),
+            pool_address: Addr::unchecked(POOL),
+            is_token1: true,
+        }]
+    );
+
+    let remove_msg = ExecuteMsg::RemovePool {
+        denom: Denom::Native("uatom".to_string()),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        remove_msg.clone(),
+    )
+    .unwrap();
+    let res: PoolsResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert!(res.list.is_empty());
+    let err = execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), remove_msg).unwrap_err();
+    assert!(matches!(err, ContractError::NotSupported {}));
+}
+
+#[test]
+fn bundle_auction_settles_every_token() {
+    let mut deps = mock_dependencies();
+    setup_contract(&mut deps, 0);
+    mock_token_owner(&mut deps, SELLER);
+    let now = mock_env().block.time.seconds();
+    let bundle_msg = |token_ids: Vec<u32>| ExecuteMsg::StartBundleSale {
+        token_ids,
+        sale_type: SaleType::Auction,
+        duration_type: DurationType::Time(now - 10, now + 100),
+        initial_price: Uint128::from(100u128),
+        reserve_price: Uint128::from(100u128),
+        denom: Denom::Native(DENOM.to_string()),
+        anti_sniping: None,
+        min_increment: None,
+        prices: vec![],
+        accepted_denoms: vec![],
+    };
+
+    // a bundle holds at least two distinct tokens of the sender
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(SELLER, &[]),
+        bundle_msg(vec![2]),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::InvalidBundle {}));
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(SELLER, &[]),
+        bundle_msg(vec![2, 2]),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::InvalidBundle {}));
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        bundle_msg(vec![2, 3]),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    // the tokens are escrowed by the collection
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(SELLER, &[]),
+        bundle_msg(vec![2, 3]),
+    )
+    .unwrap();
+    assert_eq!(res.messages[0].msg, transfer_nft(MOCK_CONTRACT_ADDR, "2"));
+    assert_eq!(res.messages[1].msg, transfer_nft(MOCK_CONTRACT_ADDR, "3"));
+
+    let msg = ExecuteMsg::ProposeBundle {
+        bundle_id: 1,
+        denom: DENOM.to_string(),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(1000, DENOM)),
+        msg,
+    )
+    .unwrap();
+
+    // every token goes to the winner and the royalties are paid once on the bundle price
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(200);
+    let msg = ExecuteMsg::SettleBundleSale { bundle_id: 1 };
+    let res = execute(deps.as_mut(), env, mock_info("anyone", &[]), msg).unwrap();
+    assert_eq!(res.messages.len(), 5);
+    assert_eq!(res.messages[0].msg, transfer_nft("alice", "2"));
+    assert_eq!(res.messages[1].msg, transfer_nft("alice", "3"));
+    assert_eq!(res.messages[2].msg, bank_send(OWNER, 20));
+    assert_eq!(res.messages[3].msg, bank_send(ARTIST, 30));
+    assert_eq!(res.messages[4].msg, bank_send(SELLER, 950));
+}
+
+// Token ids of the sales returned by msg
+fn sale_ids(deps: Deps, msg: QueryMsg) -> Vec<u32> {
+    let res: SalesResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+    res.list
+        .iter()
+        .map(|sale_info| sale_info.token_id)
+        .collect()
+}
+
+#[test]
+fn indexed_sale_queries() {
+    let mut deps = mock_dependencies();
+    setup_contract(&mut deps, 0);
+    let now = mock_env().block.time.seconds();
+    let listings = [
+        (
+            SELLER,
+            "1",
+            SaleType::Fixed,
+            DurationType::Fixed,
+            500,
+            DENOM,
+        ),
+        ("bob", "2", SaleType::Fixed, DurationType::Fixed, 300, DENOM),
+        (
+            SELLER,
+            "3",
+            SaleType::Auction,
+            DurationType::Time(now - 10, now + 100),
+            100,
+            DENOM,
+        ),
+        (
+            SELLER,
+            "4",
+            SaleType::Fixed,
+            DurationType::Fixed,
+            900,
+            "uatom",
+        ),
+    ];
+    for (seller, token_id, sale_type, duration_type, price, denom) in listings {
+        let msg = NftReceiveMsg::StartSale {
+            sale_type,
+            duration_type,
+            initial_price: Uint128::from(price as u128),
+            reserve_price: Uint128::from(price as u128),
+            denom: Denom::Native(denom.to_string()),
+            anti_sniping: None,
+            min_increment: None,
+            prices: vec![],
+            accepted_denoms: vec![],
+        };
+        send_nft(deps.as_mut(), seller, token_id, msg).unwrap();
+    }
+
+    let msg = QueryMsg::SalesBySeller {
+        seller: SELLER.to_string(),
+        start_after: None,
+        limit: None,
+    };
+    assert_eq!(sale_ids(deps.as_ref(), msg), vec![1, 3, 4]);
+    let msg = QueryMsg::SalesBySeller {
+        seller: SELLER.to_string(),
+        start_after: Some("1".to_string()),
+        limit: Some(1),
+    };
+    assert_eq!(sale_ids(deps.as_ref(), msg), vec![3]);
+
+    let msg = QueryMsg::SalesByDenom {
+        denom: Denom::Native(DENOM.to_string()),
+        start_after: None,
+        limit: None,
+    };
+    assert_eq!(sale_ids(deps.as_ref(), msg), vec![1, 2, 3]);
+
+    let msg = QueryMsg::SalesByType {
+        sale_type: SaleType::Auction,
+        start_after: None,
+        limit: None,
+    };
+    assert_eq!(sale_ids(deps.as_ref(), msg), vec![3]);
+
+    // cheapest first, within the price range
+    let price_msg = |min_price: Option<u128>| QueryMsg::SalesByPrice {
+        sale_type: SaleType::Fixed,
+        denom: Denom::Native(DENOM.to_string()),
+        min_price: min_price.map(Uint128::from),
+        max_price: None,
+        start_after: None,
+        limit: None,
+    };
+    assert_eq!(sale_ids(deps.as_ref(), price_msg(None)), vec![2, 1]);
+    assert_eq!(sale_ids(deps.as_ref(), price_msg(Some(400))), vec![1]);
+
+    let msg = QueryMsg::SalesEndingBefore {
+        time: now + 200,
+        start_after: None,
+        limit: None,
+    };
+    assert_eq!(sale_ids(deps.as_ref(), msg), vec![3]);
+    let msg = QueryMsg::SalesEndingBefore {
+        time: now + 50,
+        start_after: None,
+        limit: None,
+    };
+    assert!(sale_ids(deps.as_ref(), msg).is_empty());
+}
+
+// Sale keys address holds the top bid on
+fn bidder_bids(deps: Deps, address: &str) -> Vec<String> {
+    let msg = QueryMsg::BidsByBidder {
+        address: Addr::unchecked(address),
+        start_after: None,
+        limit: None,
+    };
+    let res: BidsByBidderResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+    res.list.into_iter().map(|bid| bid.token_id).collect()
+}
+
+#[test]
+fn bids_by_bidder_follow_the_top_bids() {
+    let mut deps = mock_dependencies();
+    setup_contract(&mut deps, 0);
+    start_auction(deps.as_mut(), 1000);
+    let now = mock_env().block.time.seconds();
+    let msg = NftReceiveMsg::StartSale {
+        sale_type: SaleType::Auction,
+        duration_type: DurationType::Time(now - 10, now + 100),
+        initial_price: Uint128::from(100u128),
+        reserve_price: Uint128::from(1000u128),
+        denom: Denom::Native(DENOM.to_string()),
+        anti_sniping: None,
+        min_increment: None,
+        prices: vec![],
+        accepted_denoms: vec![],
+    };
+    send_nft(deps.as_mut(), SELLER, "2", msg).unwrap();
+
+    bid(deps.as_mut(), "alice", 200).unwrap();
+    let msg = ExecuteMsg::Propose {
+        token_id: 2,
+        denom: DENOM.to_string(),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &coins(150, DENOM)),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(bidder_bids(deps.as_ref(), "alice"), vec!["1", "2"]);
+
+    // an outbid bid leaves the list
+    bid(deps.as_mut(), "bob", 300).unwrap();
+    assert_eq!(bidder_bids(deps.as_ref(), "alice"), vec!["2"]);
+    assert_eq!(bidder_bids(deps.as_ref(), "bob"), vec!["1"]);
+
+    // so do cancelled bids and settled sales
+    let msg = ExecuteMsg::CancelPropose { token_id: 2 };
+    execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+    assert!(bidder_bids(deps.as_ref(), "alice").is_empty());
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(200);
+    let msg = ExecuteMsg::SettleSale { token_id: 1 };
+    execute(deps.as_mut(), env, mock_info("anyone", &[]), msg).unwrap();
+    assert!(bidder_bids(deps.as_ref(), "bob").is_empty());
+}
+
+#[test]
+fn shuffled_mint_hands_out_every_id_once() {
+    let mut deps = mock_dependencies();
+    setup_contract(&mut deps, 0);
+    let mut config = CONFIG.load(&deps.storage).unwrap();
+    config.shuffle = true;
+    config.max_tokens = 6;
+    CONFIG.save(&mut deps.storage, &config).unwrap();
+
+    let mut token_ids = vec![];
+    for minter in ["alice", "bob", "carol", "dave", "erin"] {
+        let msg = ExecuteMsg::Mint {
+            uri: "ipfs://1.json".to_string(),
+            extension: None,
+            whitelist: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(minter, &[]), msg).unwrap();
+        match &res.messages[0].msg {
+            CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => match from_binary(msg).unwrap() {
+                Cw721ExecuteMsg::<Extension>::Mint(mint) => {
+                    token_ids.push(mint.token_id.parse::<u32>().unwrap())
+                }
+                _ => panic!("expected a mint"),
+            },
+            _ => panic!("expected a wasm message"),
+        }
+    }
+    token_ids.sort_unstable();
+    assert_eq!(token_ids, vec![1, 2, 3, 4, 5]);
+
+    let msg = QueryMsg::RemainingTokens {};
+    let res: RemainingTokensResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.remaining, 0);
+
+    let msg = ExecuteMsg::Mint {
+        uri: "ipfs://1.json".to_string(),
+        extension: None,
+        whitelist: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("frank", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::MaxTokensExceed {}));
+}
+
+#[test]
+fn paused_collection_keeps_the_exits_open() {
+    let mut deps = mock_dependencies();
+    setup_contract(&mut deps, 0);
+    start_auction(deps.as_mut(), 1000);
+    bid(deps.as_mut(), "alice", 200).unwrap();
+
+    let paused = PauseState {
+        minting: true,
+        listing: true,
+        bidding: true,
+        settlement: true,
+    };
+    let msg = ExecuteMsg::UpdatePauseState {
+        paused: paused.clone(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+    let res: PauseState =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::GetPauseState {}).unwrap())
+            .unwrap();
+    assert_eq!(res, paused);
+
+    let msg = ExecuteMsg::Mint {
+        uri: "ipfs://1.json".to_string(),
+        extension: None,
+        whitelist: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("minter", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::Disabled {}));
+    let err = bid(deps.as_mut(), "bob", 300).unwrap_err();
+    assert!(matches!(err, ContractError::Disabled {}));
+    let msg = NftReceiveMsg::StartSale {
+        sale_type: SaleType::Fixed,
+        duration_type: DurationType::Fixed,
+        initial_price: Uint128::from(100u128),
+        reserve_price: Uint128::from(100u128),
+        denom: Denom::Native(DENOM.to_string()),
+        anti_sniping: None,
+        min_increment: None,
+        prices: vec![],
+        accepted_denoms: vec![],
+    };
+    let err = send_nft(deps.as_mut(), SELLER, "2", msg).unwrap_err();
+    assert!(matches!(err, ContractError::Disabled {}));
+
+    // bidders and sellers can still get their funds and tokens back
+    let msg = ExecuteMsg::CancelPropose { token_id: 1 };
+    execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+    let msg = ExecuteMsg::CancelSale { token_id: 1 };
+    let res = execute(deps.as_mut(), mock_env(), mock_info(SELLER, &[]), msg).unwrap();
+    assert_eq!(res.messages[0].msg, transfer_nft(SELLER, "1"));
+    let msg = ExecuteMsg::WithdrawRefunds {};
+    let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+    assert_eq!(res.messages[0].msg, bank_send("alice", 200));
+}
@@ -1,12 +1,15 @@
-use cosmwasm_std::{StdError, Uint128};
+use cosmwasm_std::StdError;
 use thiserror::Error;
-use cw_utils::{Expiration, Scheduled};
+use hex::FromHexError;
 
 #[derive(Error, Debug)]
 pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Hex(#[from] FromHexError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
@@ -155,4 +158,46 @@ pub enum ContractError {
 
     #[error("InsufficientRoyalty")]
     InsufficientRoyalty {},
+
+    #[error("BidIncrementTooLow")]
+    BidIncrementTooLow {},
+
+    #[error("InvalidAntiSniping")]
+    InvalidAntiSniping {},
+
+    #[error("WhitelistRequired")]
+    WhitelistRequired {},
+
+    #[error("MintAllowanceExceeded")]
+    MintAllowanceExceeded {},
+
+    #[error("AlreadyOffered")]
+    AlreadyOffered {},
+
+    #[error("OfferNotFound")]
+    OfferNotFound {},
+
+    #[error("NoRefunds")]
+    NoRefunds {},
+
+    #[error("CannotCancelBid")]
+    CannotCancelBid {},
+
+    #[error("InvalidPlatformFee")]
+    InvalidPlatformFee {},
+
+    #[error("InvalidBundle")]
+    InvalidBundle {},
+
+    #[error("InvalidProvenanceHash")]
+    InvalidProvenanceHash {},
+
+    #[error("AlreadyRevealed")]
+    AlreadyRevealed {},
+
+    #[error("NoPendingOwner")]
+    NoPendingOwner {},
+
+    #[error("OwnershipTransferExpired")]
+    OwnershipTransferExpired {},
 }
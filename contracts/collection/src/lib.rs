@@ -1,7 +1,7 @@
 pub mod contract;
+mod contract_tests;
 mod error;
 pub mod msg;
 pub mod state;
 pub mod util;
-pub mod constants;
 pub use crate::error::ContractError;
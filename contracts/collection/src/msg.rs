@@ -3,9 +3,10 @@ use cw20::Cw20ReceiveMsg;
 use cw721::Cw721ReceiveMsg;
 
 use cw721_base::Extension;
+pub use cw721_base::Royalty;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use cw_utils::{Expiration, Scheduled};
+use cw_utils::Expiration;
 use cw20::Denom;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -17,24 +18,64 @@ pub struct InstantiateMsg {
     pub token_code_id: u64,
     pub maximum_royalty_fee: u32,
     pub royalties: Vec<Royalty>,
-    pub uri: String
+    pub uri: String,
+    pub mint_price: Uint128,
+    pub mint_denom: Denom,
+    // Set by the marketplace that instantiates the collection
+    pub platform_fee: Option<PlatformFee>,
+    // hex sha256 of the ordered metadata list, committed before the drop
+    pub provenance_hash: Option<String>,
+    // uri of every token until Reveal, enables the delayed reveal
+    pub placeholder_uri: Option<String>,
+    // mint token ids in pseudo random order instead of sequentially, the ids are predictable
+    // from the block so rarity should stay hidden behind the delayed reveal
+    #[serde(default)]
+    pub shuffle: bool
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
+    // Proposes the new owner, who has to send AcceptOwnership
     UpdateOwner {
         owner: Addr,
+        expires: Option<Expiration>
     },
+    AcceptOwnership {},
+    CancelOwnershipTransfer {},
+    // Pauses or resumes everything at once
     UpdateEnabled {
         enabled: bool
     },
+    UpdatePauseState {
+        paused: PauseState
+    },
     UpdateRoyalties {
         maximum_royalty_fee: u32,
         royalties: Vec<Royalty>
     },
-    Mint {uri: String, extension: Extension},
-    // Edit {token_id: u32, uri: String, extension: Extension},
+    UpdateMintPrice {
+        mint_price: Uint128,
+        mint_denom: Denom
+    },
+    // Registers the wasmswap pool used to swap denom
+    AddPool {
+        denom: Denom,
+        pool_address: Addr
+    },
+    RemovePool {
+        denom: Denom
+    },
+    RegisterMerkleRoot {
+        merkle_root: Option<String>
+    },
+    Mint {uri: String, extension: Extension, whitelist: Option<WhitelistProof>},
+    // uri or extension None keeps the current one, a uri set after the reveal wins over the base uri
+    Edit {token_id: u32, uri: Option<String>, extension: Extension},
+    // Freezes the metadata of the token, or of every token when token_id is None, for good
+    FreezeMetadata {
+        token_id: Option<u32>
+    },
     BatchMint {
         uri: Vec<String>, 
         extension:Vec<Extension>,
@@ -44,6 +85,12 @@ pub enum ExecuteMsg {
         token_id: u32,
         denom: String
     },
+    // Buys a fixed price listing with another token, swapped into the listing denom
+    SwapPropose {
+        token_id: u32,
+        denom: String,
+        min_output: Uint128
+    },
     Receive(Cw20ReceiveMsg),
     ReceiveNft(Cw721ReceiveMsg),
     AcceptSale {
@@ -52,6 +99,38 @@ pub enum ExecuteMsg {
     CancelSale {
         token_id: u32,
     },
+    SettleSale {
+        token_id: u32
+    },
+    // The collection must be approved for every token of the bundle
+    StartBundleSale {
+        token_ids: Vec<u32>,
+        sale_type: SaleType,
+        duration_type: DurationType,
+        initial_price: Uint128,
+        reserve_price: Uint128,
+        denom: Denom,
+        anti_sniping: Option<AntiSniping>,
+        min_increment: Option<BidIncrement>,
+        prices: Vec<DenomPrice>,
+        accepted_denoms: Vec<Denom>
+    },
+    ProposeBundle {
+        bundle_id: u32,
+        denom: String
+    },
+    AcceptBundleSale {
+        bundle_id: u32
+    },
+    CancelBundleSale {
+        bundle_id: u32
+    },
+    SettleBundleSale {
+        bundle_id: u32
+    },
+    CancelBundlePropose {
+        bundle_id: u32
+    },
     ChangeContract {
         cw721_address: Addr
     },
@@ -61,17 +140,39 @@ pub enum ExecuteMsg {
     UpdateUnusedTokenId {
         token_id: u32
     },
+    // Switches every token to base_uri/{token_id}, only once
+    Reveal {
+        base_uri: String
+    },
     EditSale {
         token_id: u32,
         sale_type: SaleType,
         duration_type: DurationType,
         initial_price: Uint128,
         reserve_price: Uint128,
-        denom: Denom
+        denom: Denom,
+        anti_sniping: Option<AntiSniping>,
+        min_increment: Option<BidIncrement>,
+        prices: Vec<DenomPrice>,
+        accepted_denoms: Vec<Denom>
     },
+    // Credits the bid to the refunds, refused once it meets the reserve or the auction is over
     CancelPropose {
         token_id: u32
-    }
+    },
+    Offer {
+        token_id: u32,
+        denom: String,
+        expires: Expiration
+    },
+    CancelOffer {
+        token_id: u32
+    },
+    CollectionBid {
+        denom: String
+    },
+    CancelCollectionBid {},
+    WithdrawRefunds {}
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -79,7 +180,24 @@ pub enum ExecuteMsg {
 pub enum ReceiveMsg {
     Propose {
         token_id: u32
-    }
+    },
+    ProposeBundle {
+        bundle_id: u32
+    },
+    SwapPropose {
+        token_id: u32,
+        min_output: Uint128
+    },
+    Mint {
+        uri: String,
+        extension: Extension,
+        whitelist: Option<WhitelistProof>
+    },
+    Offer {
+        token_id: u32,
+        expires: Expiration
+    },
+    CollectionBid {}
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -90,7 +208,17 @@ pub enum NftReceiveMsg {
         duration_type: DurationType,
         initial_price: Uint128,
         reserve_price: Uint128,
-        denom: Denom
+        denom: Denom,
+        anti_sniping: Option<AntiSniping>,
+        min_increment: Option<BidIncrement>,
+        prices: Vec<DenomPrice>,
+        accepted_denoms: Vec<Denom>
+    },
+    AcceptOffer {
+        bidder: String
+    },
+    AcceptCollectionBid {
+        bidder: String
     }
 }
 
@@ -98,14 +226,120 @@ pub enum NftReceiveMsg {
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     GetConfig {},
+    GetPendingOwner {},
     GetSale {
         token_id: u32,
     },
+    GetBundleSale {
+        bundle_id: u32
+    },
     GetSales {
         start_after: Option<u32>,
         limit: Option<u32>
     },
-    
+    // start_after of the indexed sale queries is the sale key, the token id or "bundle_<id>"
+    SalesBySeller {
+        seller: String,
+        start_after: Option<String>,
+        limit: Option<u32>
+    },
+    SalesByDenom {
+        denom: Denom,
+        start_after: Option<String>,
+        limit: Option<u32>
+    },
+    SalesByType {
+        sale_type: SaleType,
+        start_after: Option<String>,
+        limit: Option<u32>
+    },
+    // Token sales listed in denom, cheapest first. Auctions are listed at their top bid or
+    // initial price, dutch auctions at their reserve price.
+    // start_after is the (listed price, sale key) of the last sale of the previous page
+    SalesByPrice {
+        sale_type: SaleType,
+        denom: Denom,
+        min_price: Option<Uint128>,
+        max_price: Option<Uint128>,
+        start_after: Option<(Uint128, String)>,
+        limit: Option<u32>
+    },
+    SalesEndingBefore {
+        time: u64,
+        start_after: Option<(u64, String)>,
+        limit: Option<u32>
+    },
+    CurrentPrice {
+        token_id: u32
+    },
+    GetMerkleRoot {},
+    GetPauseState {},
+    RemainingTokens {},
+    // metadata is the ordered list the provenance hash was computed from
+    VerifyProvenance {
+        metadata: Vec<String>
+    },
+    GetWhitelistMinted {
+        address: Addr
+    },
+    GetOffers {
+        token_id: u32,
+        start_after: Option<String>,
+        limit: Option<u32>
+    },
+    GetOffersByBidder {
+        bidder: Addr,
+        start_after: Option<u32>,
+        limit: Option<u32>
+    },
+    GetCollectionBid {
+        bidder: Addr
+    },
+    // Highest first, start_after is the (price, bidder) of the last bid of the previous page
+    GetCollectionBids {
+        denom: Denom,
+        start_after: Option<(Uint128, String)>,
+        limit: Option<u32>
+    },
+    GetBestCollectionBid {
+        denom: Denom
+    },
+    PendingRefunds {
+        address: Addr
+    },
+    // Auctions the address holds the top bid on, outbid bids move to the pending refunds
+    BidsByBidder {
+        address: Addr,
+        start_after: Option<String>,
+        limit: Option<u32>
+    },
+    // newest first
+    GetTrades {
+        start_after: Option<u64>,
+        limit: Option<u32>
+    },
+    GetVolumes {
+        start_after: Option<Denom>,
+        limit: Option<u32>
+    },
+    GetLastSale {
+        token_id: u32
+    },
+    GetLastSales {
+        start_after: Option<String>,
+        limit: Option<u32>
+    },
+    // lowest active fixed listing of a single token priced in denom
+    FloorPrice {
+        denom: Denom
+    },
+    GetPool {
+        denom: Denom
+    },
+    GetPools {
+        start_after: Option<Denom>,
+        limit: Option<u32>
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -119,20 +353,48 @@ pub struct ConfigResponse {
     pub maximum_royalty_fee: u32,
     pub royalties: Vec<Royalty>,
     pub uri: String,
-    pub enabled: bool
+    // true while nothing is paused
+    pub enabled: bool,
+    pub paused: PauseState,
+    pub mint_price: Uint128,
+    pub mint_denom: Denom,
+    pub platform_fee: Option<PlatformFee>,
+    pub provenance_hash: Option<String>,
+    pub placeholder_uri: Option<String>,
+    pub base_uri: Option<String>,
+    pub shuffle: bool
 }
 
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Request {
     pub address: Addr,
+    // value of the bid in the denom of the sale
+    pub price: Uint128,
+    // funds held for the bid, paid out or refunded as is. Bids swapped through a pool hold
+    // the swap output, so they are refunded in the denom of the sale, not the denom they were sent in
+    #[serde(default = "legacy_request_denom")]
+    pub denom: Denom,
+    #[serde(default)]
+    pub amount: Uint128
+}
+
+// Requests stored before the held funds were recorded, the migration fills in the sale denom and price
+fn legacy_request_denom() -> Denom {
+    Denom::Native(String::new())
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DenomPrice {
+    pub denom: Denom,
     pub price: Uint128
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub enum SaleType {
     Fixed,
-    Auction
+    Auction,
+    Dutch
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -150,8 +412,49 @@ pub enum DurationType {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct Royalty {
+pub struct AntiSniping {
+    /// Seconds before the end of the auction in which a bid extends it
+    pub window: u64,
+    /// Seconds the auction keeps running after such a bid
+    pub extension: u64
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub enum BidIncrement {
+    Absolute(Uint128),
+    // 10000 = 100%
+    BasisPoints(u32)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PoolInfo {
+    pub denom: Denom,
+    pub pool_address: Addr,
+    // true when denom is the token1 of the pool
+    pub is_token1: bool
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PoolsResponse {
+    pub list: Vec<PoolInfo>
+}
+
+// Cancelling sales, bids and offers and withdrawing refunds are never paused
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct PauseState {
+    pub minting: bool,
+    // new sales
+    pub listing: bool,
+    // purchases, bids, offers and collection bids
+    pub bidding: bool,
+    // accepting and settling sales, accepting offers and collection bids
+    pub settlement: bool
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PlatformFee {
     pub address: Addr,
+    // in millionths of the sale price, same as royalties
     pub rate: u32
 }
 
@@ -163,18 +466,173 @@ pub struct SaleInfo {
     pub duration_type: DurationType,
     pub initial_price: Uint128,
     pub reserve_price: Uint128,
+    // Auctions only keep the highest bid, outbid bidders are credited to the refund ledger
     pub requests: Vec<Request>,
+    #[serde(default)]
+    pub bid_count: u32,
     pub denom: Denom,
-    pub can_accept: bool 
+    pub can_accept: bool,
+    pub anti_sniping: Option<AntiSniping>,
+    pub min_increment: Option<BidIncrement>,
+    // Fixed sales: price in each other accepted denom
+    #[serde(default)]
+    pub prices: Vec<DenomPrice>,
+    // Auctions: other denoms accepted for bids, swapped into denom through the pool registry
+    #[serde(default)]
+    pub accepted_denoms: Vec<Denom>,
+    // Tokens of a bundle listing, token_id is then the bundle id
+    pub bundle: Option<Vec<u32>>
+}
+
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CurrentPriceResponse {
+    pub token_id: u32,
+    pub price: Uint128,
+    pub denom: Denom
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WhitelistProof {
+    /// Mint allowance committed in the merkle leaf (sha256(address + amount))
+    pub amount: u32,
+    pub proof: Vec<String>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RemainingTokensResponse {
+    pub remaining: u32
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VerifyProvenanceResponse {
+    pub provenance_hash: Option<String>,
+    pub verified: bool
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MerkleRootResponse {
+    pub merkle_root: Option<String>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WhitelistMintedResponse {
+    pub address: Addr,
+    pub minted: u32
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct SalesResponse {
     pub list: Vec<SaleInfo>
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Offer {
+    pub token_id: u32,
+    pub bidder: Addr,
+    pub price: Uint128,
+    pub denom: Denom,
+    pub expires: Expiration
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OffersResponse {
+    pub list: Vec<Offer>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingRefund {
+    pub denom: Denom,
+    pub amount: Uint128
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Trade {
+    pub id: u64,
+    // tokens sold, every token of a bundle
+    pub token_ids: Vec<u32>,
+    pub seller: Addr,
+    pub buyer: Addr,
+    pub price: Uint128,
+    pub denom: Denom,
+    pub time: u64
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TradesResponse {
+    pub list: Vec<Trade>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Volume {
+    pub denom: Denom,
+    pub amount: Uint128,
+    pub trade_count: u64
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VolumesResponse {
+    pub trade_count: u64,
+    pub list: Vec<Volume>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FloorPriceResponse {
+    pub denom: Denom,
+    pub token_id: Option<u32>,
+    pub price: Option<Uint128>
+}
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BidderBid {
+    // sale key, the token id or "bundle_<id>"
+    pub token_id: String,
+    pub amount: Uint128,
+    pub denom: Denom
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct MigrateMsg {}
\ No newline at end of file
+pub struct BidsByBidderResponse {
+    pub address: Addr,
+    pub list: Vec<BidderBid>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingRefundsResponse {
+    pub address: Addr,
+    pub refunds: Vec<PendingRefund>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollectionBid {
+    pub bidder: Addr,
+    pub price: Uint128,
+    pub denom: Denom
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollectionBidsResponse {
+    pub list: Vec<CollectionBid>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BestCollectionBidResponse {
+    pub bid: Option<CollectionBid>
+}
+
+
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingOwner {
+    pub address: Addr,
+    // AcceptOwnership fails once expired
+    pub expires: Option<Expiration>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingOwnerResponse {
+    pub pending_owner: Option<PendingOwner>
+}
@@ -1,12 +1,12 @@
-use cw721_base::Extension;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::{Addr, Uint128};
 use cw_storage_plus::Item;
-use cw_utils::{Expiration, Scheduled};
-use cw_storage_plus::{Map};
-use crate::msg::{SaleInfo, Royalty};
+use cw_storage_plus::{Map, Index, IndexList, IndexedMap, MultiIndex};
+use crate::msg::{SaleInfo, SaleType, DurationType, Request, Trade, Volume, Royalty, Offer, CollectionBid, PendingRefund, PlatformFee, PoolInfo, PauseState, PendingOwner};
+use crate::util::denom_key;
+use cw20::Denom;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
@@ -19,16 +19,177 @@ pub struct Config {
     pub maximum_royalty_fee: u32,
     pub royalties: Vec<Royalty>,
     pub uri: String,
-    pub enabled: bool
+    #[serde(default)]
+    pub paused: PauseState,
+    #[serde(default)]
+    pub mint_price: Uint128,
+    #[serde(default = "default_mint_denom")]
+    pub mint_denom: Denom,
+    #[serde(default)]
+    pub platform_fee: Option<PlatformFee>,
+    #[serde(default)]
+    pub provenance_hash: Option<String>,
+    #[serde(default)]
+    pub placeholder_uri: Option<String>,
+    #[serde(default)]
+    pub base_uri: Option<String>,
+    #[serde(default)]
+    pub shuffle: bool
 }
 
+// Collections deployed before the mint price minted for free
+fn default_mint_denom() -> Denom {
+    Denom::Native("ujuno".to_string())
+}
+
+
 
+// Purchase or auction bid waiting for the output of its swap
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingSwap {
+    // sale key, the token id or "bundle_<id>"
+    pub key: String,
+    pub buyer: Addr,
+    pub denom: Denom,
+    pub balance_before: Uint128,
+    pub is_bid: bool
+}
 
 pub const CONFIG_KEY: &str = "config";
 pub const CONFIG: Item<Config> = Item::new(CONFIG_KEY);
 
+// Single switch of the configs stored before the pause flags, read once by the migration
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyEnabled {
+    #[serde(default)]
+    pub enabled: Option<bool>
+}
+
+pub const LEGACY_ENABLED: Item<LegacyEnabled> = Item::new(CONFIG_KEY);
+
+// Owner proposed by the current owner, takes over once it sends AcceptOwnership
+pub const PENDING_OWNER_KEY: &str = "pending_owner";
+pub const PENDING_OWNER: Item<PendingOwner> = Item::new(PENDING_OWNER_KEY);
+
 pub const SALE_KEY: &str = "sale";
-pub const SALE: Map<String, SaleInfo> = Map::new(SALE_KEY);
+
+pub struct SaleIndexes<'a> {
+    pub provider: MultiIndex<'a, Addr, SaleInfo, String>,
+    pub denom: MultiIndex<'a, String, SaleInfo, String>,
+    pub sale_type: MultiIndex<'a, String, SaleInfo, String>,
+    // end time of timed sales, u64::MAX for the others
+    pub end_time: MultiIndex<'a, u64, SaleInfo, String>,
+    pub price: MultiIndex<'a, String, SaleInfo, String>,
+}
+
+impl<'a> IndexList<SaleInfo> for SaleIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<SaleInfo>> + '_> {
+        let v: Vec<&dyn Index<SaleInfo>> = vec![&self.provider, &self.denom, &self.sale_type, &self.end_time, &self.price];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn sales<'a>() -> IndexedMap<'a, String, SaleInfo, SaleIndexes<'a>> {
+    let indexes = SaleIndexes {
+        provider: MultiIndex::new(|s: &SaleInfo| s.provider.clone(), SALE_KEY, "sale__provider"),
+        denom: MultiIndex::new(|s: &SaleInfo| denom_key(s.denom.clone()), SALE_KEY, "sale__denom"),
+        sale_type: MultiIndex::new(|s: &SaleInfo| sale_type_key(&s.sale_type), SALE_KEY, "sale__sale_type"),
+        end_time: MultiIndex::new(sale_end_time, SALE_KEY, "sale__end_time"),
+        price: MultiIndex::new(sale_price_key, SALE_KEY, "sale__price"),
+    };
+    IndexedMap::new(SALE_KEY, indexes)
+}
+
+pub fn sale_type_key(sale_type: &SaleType) -> String {
+    match sale_type {
+        SaleType::Fixed => "fixed".to_string(),
+        SaleType::Auction => "auction".to_string(),
+        SaleType::Dutch => "dutch".to_string()
+    }
+}
+
+pub fn sale_end_time(sale_info: &SaleInfo) -> u64 {
+    match sale_info.duration_type {
+        DurationType::Time(_start, end) => end,
+        _ => u64::MAX
+    }
+}
+
+// Fixed sales are listed at their price, auctions at their top bid or initial price
+// and dutch auctions at their reserve price. Bundles are kept apart from the token sales
+pub fn sale_price_key(sale_info: &SaleInfo) -> String {
+    let listed_price = match sale_info.sale_type {
+        SaleType::Fixed => sale_info.initial_price,
+        SaleType::Auction => sale_info.requests.last().map_or(sale_info.initial_price, |top| top.price),
+        SaleType::Dutch => sale_info.reserve_price
+    };
+    let sale_type = match sale_info.bundle {
+        Some(_) => format!("bundle_{}", sale_type_key(&sale_info.sale_type)),
+        None => sale_type_key(&sale_info.sale_type)
+    };
+    price_key(&sale_type, sale_info.denom.clone(), listed_price)
+}
+
+// Uint128 has at most 39 digits, padding keeps the byte order equal to the price order
+pub fn price_key(sale_type: &str, denom: Denom, price: Uint128) -> String {
+    format!("{}:{}:{:0>39}", sale_type, denom_key(denom), price.to_string())
+}
 // pub const PRICE_KEY: &str = "price";
 // pub const PRICE: Map<u32, Uint128> = Map::new(PRICE_KEY);
 
+// denom key -> wasmswap pool registered for the denom
+pub const POOLS_KEY: &str = "pools";
+pub const POOLS: Map<&str, PoolInfo> = Map::new(POOLS_KEY);
+
+pub const PENDING_SWAP_KEY: &str = "pending_swap";
+pub const PENDING_SWAP: Item<PendingSwap> = Item::new(PENDING_SWAP_KEY);
+
+pub const BUNDLE_COUNT_KEY: &str = "bundle_count";
+pub const BUNDLE_COUNT: Item<u32> = Item::new(BUNDLE_COUNT_KEY);
+
+// Shuffled mint pool: index -> token id, a missing index holds the token id index + 1.
+// The pool keeps max_tokens - unused_token_id ids, picks swap the last index into the hole
+pub const AVAILABLE_IDS_KEY: &str = "available_ids";
+pub const AVAILABLE_IDS: Map<u32, u32> = Map::new(AVAILABLE_IDS_KEY);
+
+pub const MERKLE_ROOT_KEY: &str = "merkle_root";
+pub const MERKLE_ROOT: Item<String> = Item::new(MERKLE_ROOT_KEY);
+
+pub const WHITELIST_MINTED_KEY: &str = "whitelist_minted";
+pub const WHITELIST_MINTED: Map<Addr, u32> = Map::new(WHITELIST_MINTED_KEY);
+
+pub const OFFERS_KEY: &str = "offers";
+pub const OFFERS: Map<(&str, &Addr), Offer> = Map::new(OFFERS_KEY);
+
+// Same offers keyed by bidder first, kept in sync with OFFERS
+pub const OFFERS_BY_BIDDER_KEY: &str = "offers_by_bidder";
+pub const OFFERS_BY_BIDDER: Map<(&Addr, &str), Offer> = Map::new(OFFERS_BY_BIDDER_KEY);
+
+pub const COLLECTION_BIDS_KEY: &str = "collection_bids";
+pub const COLLECTION_BIDS: Map<&Addr, CollectionBid> = Map::new(COLLECTION_BIDS_KEY);
+
+// (denom key, zero padded price + bidder) -> bidder, so bids of a denom range in price order
+pub const COLLECTION_BID_RANKING_KEY: &str = "collection_bid_ranking";
+pub const COLLECTION_BID_RANKING: Map<(&str, &str), Addr> = Map::new(COLLECTION_BID_RANKING_KEY);
+
+// (address, denom key) -> funds the address can withdraw
+pub const REFUNDS_KEY: &str = "refunds";
+pub const REFUNDS: Map<(&Addr, &str), PendingRefund> = Map::new(REFUNDS_KEY);
+
+pub const TRADE_COUNT_KEY: &str = "trade_count";
+pub const TRADE_COUNT: Item<u64> = Item::new(TRADE_COUNT_KEY);
+
+pub const TRADES_KEY: &str = "trades";
+pub const TRADES: Map<u64, Trade> = Map::new(TRADES_KEY);
+
+// denom key -> volume traded in the denom
+pub const VOLUMES_KEY: &str = "volumes";
+pub const VOLUMES: Map<&str, Volume> = Map::new(VOLUMES_KEY);
+
+// token id -> last trade of the token, bundle trades are saved under each token
+pub const LAST_SALES_KEY: &str = "last_sales";
+pub const LAST_SALES: Map<&str, Trade> = Map::new(LAST_SALES_KEY);
+
+// (bidder, sale key) -> bid of the bidder still held by the sale
+pub const BIDS_KEY: &str = "bids";
+pub const BIDS: Map<(&Addr, &str), Request> = Map::new(BIDS_KEY);
@@ -1,12 +1,17 @@
 use cosmwasm_std::{
     to_binary,  Response, StdResult, Uint128, Coin, BankMsg,
-    WasmMsg, WasmQuery, QueryRequest, Addr, Storage, CosmosMsg,  QuerierWrapper, BalanceResponse as NativeBalanceResponse, BankQuery
+    WasmMsg, WasmQuery, QueryRequest, Addr, Storage, CosmosMsg, BlockInfo, Api, Env,  QuerierWrapper, BalanceResponse as NativeBalanceResponse, BankQuery
 };
 use cw20::{Balance, Cw20ExecuteMsg, Denom, BalanceResponse as CW20BalanceResponse, Cw20QueryMsg};
 use crate::error::ContractError;
-use crate::state::CONFIG;
-use crate::msg::Royalty;
+use crate::state::{Config, CONFIG, PENDING_OWNER, REFUNDS, POOLS, AVAILABLE_IDS, TRADE_COUNT, TRADES, VOLUMES, LAST_SALES};
+use crate::msg::{Royalty, PendingRefund, PoolInfo, Trade, Volume, PauseState, PendingOwner, PlatformFee};
+use crate::contract::MULTIPLY;
+use cw_utils::Expiration;
+use cw721_base::{Extension, msg::ExecuteMsg as Cw721ExecuteMsg};
 use wasmswap::msg::{ExecuteMsg as WasmswapExecuteMsg, QueryMsg as WasmswapQueryMsg, Token1ForToken2PriceResponse, Token2ForToken1PriceResponse, InfoResponse as WasmswapInfoResponse, TokenSelect};
+use sha2::Digest;
+use std::convert::TryInto;
 
 pub const MAX_LIMIT: u32 = 30;
 pub const DEFAULT_LIMIT: u32 = 10;
@@ -15,11 +20,25 @@ pub const MAX_ORDER: u64 = 10;
 pub fn multiple() -> Uint128 { Uint128::from(100u128) }
 pub fn decimal() -> Uint128 { Uint128::from(1000000u128) }
 
+pub enum Activity {
+    Minting,
+    Listing,
+    Bidding,
+    Settlement
+}
+
 pub fn check_enabled(
     storage: &mut dyn Storage,
+    activity: Activity
 ) -> Result<Response, ContractError> {
-    let cfg = CONFIG.load(storage)?;
-    if !cfg.enabled {
+    let paused = CONFIG.load(storage)?.paused;
+    let is_paused = match activity {
+        Activity::Minting => paused.minting,
+        Activity::Listing => paused.listing,
+        Activity::Bidding => paused.bidding,
+        Activity::Settlement => paused.settlement
+    };
+    if is_paused {
         return Err(ContractError::Disabled {})
     }
     Ok(Response::new().add_attribute("action", "check_enabled"))
@@ -39,18 +58,61 @@ pub fn check_owner(
 
 pub fn execute_update_owner(
     storage: &mut dyn Storage,
+    api: &dyn Api,
     address: Addr,
     owner: Addr,
+    expires: Option<Expiration>
 ) -> Result<Response, ContractError> {
     // authorize owner
     check_owner(storage, address)?;
-    
+    // a mistyped owner could never accept
+    let owner = api.addr_validate(owner.as_str())?;
+
+    PENDING_OWNER.save(storage, &PendingOwner {
+        address: owner.clone(),
+        expires
+    })?;
+
+    Ok(Response::new().add_attribute("action", "propose_owner").add_attribute("pending_owner", owner))
+}
+
+pub fn execute_accept_ownership(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    address: Addr
+) -> Result<Response, ContractError> {
+    let pending = PENDING_OWNER.may_load(storage)?.ok_or(ContractError::NoPendingOwner {})?;
+    if pending.address != address {
+        return Err(ContractError::Unauthorized {});
+    }
+    if let Some(expires) = pending.expires {
+        if expires.is_expired(block) {
+            return Err(ContractError::OwnershipTransferExpired {});
+        }
+    }
+
     CONFIG.update(storage, |mut exists| -> StdResult<_> {
-        exists.owner = owner.clone();
+        exists.owner = address.clone();
         Ok(exists)
     })?;
+    PENDING_OWNER.remove(storage);
 
-    Ok(Response::new().add_attribute("action", "update_config").add_attribute("owner", owner.clone()))
+    Ok(Response::new().add_attribute("action", "accept_owner").add_attribute("owner", address.clone()))
+}
+
+pub fn execute_cancel_ownership_transfer(
+    storage: &mut dyn Storage,
+    address: Addr
+) -> Result<Response, ContractError> {
+    // authorize owner
+    check_owner(storage, address)?;
+
+    if PENDING_OWNER.may_load(storage)?.is_none() {
+        return Err(ContractError::NoPendingOwner {});
+    }
+    PENDING_OWNER.remove(storage);
+
+    Ok(Response::new().add_attribute("action", "cancel_owner"))
 }
 
 pub fn execute_update_enabled (
@@ -62,13 +124,40 @@ pub fn execute_update_enabled (
     check_owner(storage, address)?;
     
     CONFIG.update(storage, |mut exists| -> StdResult<_> {
-        exists.enabled = enabled;
+        exists.paused = PauseState {
+            minting: !enabled,
+            listing: !enabled,
+            bidding: !enabled,
+            settlement: !enabled
+        };
         Ok(exists)
     })?;
 
     Ok(Response::new().add_attribute("action", "update_enabled"))
 }
 
+pub fn execute_update_pause_state (
+    storage: &mut dyn Storage,
+    address: Addr,
+    paused: PauseState
+) -> Result<Response, ContractError> {
+    // authorize owner
+    check_owner(storage, address)?;
+
+    CONFIG.update(storage, |mut exists| -> StdResult<_> {
+        exists.paused = paused.clone();
+        Ok(exists)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_pause_state")
+        .add_attribute("minting", paused.minting.to_string())
+        .add_attribute("listing", paused.listing.to_string())
+        .add_attribute("bidding", paused.bidding.to_string())
+        .add_attribute("settlement", paused.settlement.to_string())
+    )
+}
+
 pub fn execute_update_royalties (
     storage: &mut dyn Storage,
     address: Addr,
@@ -86,14 +175,110 @@ pub fn execute_update_royalties (
     if sum > maximum_royalty_fee {
         return Err(crate::ContractError::ExceedsMaximumRoyaltyFee {});
     }
+    check_platform_fee(&CONFIG.load(storage)?.platform_fee, maximum_royalty_fee)?;
     
-    CONFIG.update(storage, |mut exists| -> StdResult<_> {
+    let cfg = CONFIG.update(storage, |mut exists| -> StdResult<_> {
         exists.maximum_royalty_fee = maximum_royalty_fee;
-        exists.royalties = royalties;
+        exists.royalties = royalties.clone();
+        Ok(exists)
+    })?;
+
+    //Keep the default royalties reported by the cw721 contract in sync
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    if let Some(cw721_address) = cfg.cw721_address {
+        msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: cw721_address.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw721ExecuteMsg::<Extension>::UpdateDefaultRoyalties {
+                royalties: Some(royalties)
+            })?,
+        }));
+    }
+
+    Ok(Response::new().add_messages(msgs).add_attribute("action", "update_royalties"))
+}
+
+// The platform fee and the royalties together can not exceed the sale price
+pub fn check_platform_fee(
+    platform_fee: &Option<PlatformFee>,
+    maximum_royalty_fee: u32
+) -> Result<(), ContractError> {
+    let rate = platform_fee.as_ref().map_or(0, |platform_fee| platform_fee.rate);
+    if rate as u64 + maximum_royalty_fee as u64 > MULTIPLY as u64 {
+        return Err(ContractError::InvalidPlatformFee {});
+    }
+    Ok(())
+}
+
+// Token level royalties are held to the same cap as the collection royalties
+pub fn check_extension_royalties(
+    storage: &mut dyn Storage,
+    extension: &Extension
+) -> Result<(), ContractError> {
+    let royalties = match extension {
+        Some(metadata) => match metadata.royalties.clone() {
+            Some(royalties) => royalties,
+            None => return Ok(())
+        },
+        None => return Ok(())
+    };
+    let cfg = CONFIG.load(storage)?;
+    let mut sum = 0;
+    for item in royalties {
+        sum += item.rate;
+    }
+    if sum > cfg.maximum_royalty_fee {
+        return Err(crate::ContractError::ExceedsMaximumRoyaltyFee {});
+    }
+    Ok(())
+}
+
+pub fn execute_update_mint_price (
+    storage: &mut dyn Storage,
+    address: Addr,
+    mint_price: Uint128,
+    mint_denom: Denom
+) -> Result<Response, ContractError> {
+    // authorize owner
+    check_owner(storage, address)?;
+    
+    CONFIG.update(storage, |mut exists| -> StdResult<_> {
+        exists.mint_price = mint_price;
+        exists.mint_denom = mint_denom;
         Ok(exists)
     })?;
 
-    Ok(Response::new().add_attribute("action", "update_royalties"))
+    Ok(Response::new().add_attribute("action", "update_mint_price").add_attribute("mint_price", mint_price))
+}
+
+// Leaves and pairs are hashed like helpers/src/airdrop.ts: sha256(address + amount), sorted pairs
+pub fn verify_merkle_proof(
+    merkle_root: String,
+    leaf: String,
+    proof: Vec<String>
+) -> Result<(), ContractError> {
+    let hash: [u8; 32] = sha2::Sha256::digest(leaf.as_bytes())
+        .as_slice()
+        .try_into()
+        .map_err(|_| ContractError::WrongLength {})?;
+
+    let hash = proof.into_iter().try_fold(hash, |hash, p| -> Result<[u8; 32], ContractError> {
+        let mut proof_buf = [0; 32];
+        hex::decode_to_slice(p, &mut proof_buf)?;
+        let mut hashes = [hash, proof_buf];
+        hashes.sort_unstable();
+        sha2::Sha256::digest(&hashes.concat())
+            .as_slice()
+            .try_into()
+            .map_err(|_| ContractError::WrongLength {})
+    })?;
+
+    let mut root_buf: [u8; 32] = [0; 32];
+    hex::decode_to_slice(merkle_root, &mut root_buf)?;
+    if root_buf != hash {
+        return Err(ContractError::VerificationFailed {});
+    }
+    Ok(())
 }
 
 pub fn check_token_and_pool (
@@ -120,6 +305,63 @@ pub fn check_token_and_pool (
     return Err(ContractError::PoolAndTokenMismatch{});
 }
 
+pub fn execute_add_pool (
+    storage: &mut dyn Storage,
+    querier: QuerierWrapper,
+    address: Addr,
+    denom: Denom,
+    pool_address: Addr
+) -> Result<Response, ContractError> {
+    // authorize owner
+    check_owner(storage, address)?;
+
+    let is_token1 = check_token_and_pool(querier, denom.clone(), pool_address.clone())?;
+    POOLS.save(storage, &denom_key(denom.clone()), &PoolInfo {
+        denom,
+        pool_address: pool_address.clone(),
+        is_token1
+    })?;
+
+    Ok(Response::new().add_attribute("action", "add_pool").add_attribute("pool_address", pool_address))
+}
+
+pub fn execute_remove_pool (
+    storage: &mut dyn Storage,
+    address: Addr,
+    denom: Denom
+) -> Result<Response, ContractError> {
+    // authorize owner
+    check_owner(storage, address)?;
+
+    let key = denom_key(denom);
+    if !POOLS.has(storage, &key) {
+        return Err(ContractError::NotSupported {});
+    }
+    POOLS.remove(storage, &key);
+
+    Ok(Response::new().add_attribute("action", "remove_pool"))
+}
+
+// Pool registered for denom, when its other token is other_denom, and whether denom is its token1
+pub fn find_swap_pool (
+    storage: &mut dyn Storage,
+    querier: QuerierWrapper,
+    denom: Denom,
+    other_denom: Denom
+) -> Result<(Addr, bool), ContractError> {
+    let pool = POOLS.may_load(storage, &denom_key(denom))?.ok_or(ContractError::NotSupported {})?;
+
+    let pool_info_response: WasmswapInfoResponse = querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: pool.pool_address.clone().into(),
+        msg: to_binary(&WasmswapQueryMsg::Info {})?,
+    }))?;
+    let output_denom = if pool.is_token1 { pool_info_response.token2_denom } else { pool_info_response.token1_denom };
+    if output_denom != other_denom {
+        return Err(ContractError::PoolAndTokenMismatch{});
+    }
+    Ok((pool.pool_address, pool.is_token1))
+}
+
 pub fn get_amount_of_denom(
     balance: Balance,
     denom: Denom
@@ -271,6 +513,113 @@ pub fn swap_token_messages(
 }
 
 
+// Storage key of a denom, used to group data per payment token
+pub fn denom_key(
+    denom: Denom
+) -> String {
+    match denom {
+        Denom::Native(native_str) => format!("native:{}", native_str),
+        Denom::Cw20(cw20_address) => format!("cw20:{}", cw20_address)
+    }
+}
+
+// Removes a random token id from the shuffled mint pool, O(1) through the swap with the last index.
+// The seed hashes the block, the minter and the mint counter. All of them are known before the
+// mint executes, so a minter or a validator can predict the id and choose when to mint.
+// The shuffle spreads the ids over the mint but does not hide them, the provenance hash and
+// the delayed reveal are what keep the metadata of an id unknown until the reveal
+pub fn pick_token_id(
+    storage: &mut dyn Storage,
+    env: &Env,
+    minter: &Addr,
+    config: &Config
+) -> Result<u32, ContractError> {
+    let remaining = config.max_tokens - config.unused_token_id;
+    if remaining == 0 {
+        return Err(ContractError::MaxTokensExceed {});
+    }
+
+    let seed = sha2::Sha256::digest(format!(
+        "{}{}{}{}",
+        env.block.height,
+        env.block.time.nanos(),
+        minter,
+        config.unused_token_id
+    ).as_bytes());
+    let random = u64::from_be_bytes(seed[0..8].try_into().unwrap());
+    let index = (random % remaining as u64) as u32;
+    let last = remaining - 1;
+
+    let token_id = AVAILABLE_IDS.may_load(storage, index)?.unwrap_or(index + 1);
+    let last_id = AVAILABLE_IDS.may_load(storage, last)?.unwrap_or(last + 1);
+    if index != last {
+        AVAILABLE_IDS.save(storage, index, &last_id)?;
+    }
+    AVAILABLE_IDS.remove(storage, last);
+
+    Ok(token_id)
+}
+
+// Adds amount to the refunds the address can withdraw later
+pub fn credit_refund(
+    storage: &mut dyn Storage,
+    address: Addr,
+    denom: Denom,
+    amount: Uint128
+) -> Result<(), ContractError> {
+    if amount == Uint128::zero() {
+        return Ok(());
+    }
+    let key = denom_key(denom.clone());
+    let mut refund = REFUNDS.may_load(storage, (&address, &key))?.unwrap_or(PendingRefund {
+        denom,
+        amount: Uint128::zero()
+    });
+    refund.amount += amount;
+    REFUNDS.save(storage, (&address, &key), &refund)?;
+    Ok(())
+}
+
+// Appends a completed sale to the trade log and the volume statistics
+pub fn record_trade(
+    storage: &mut dyn Storage,
+    time: u64,
+    token_ids: Vec<u32>,
+    seller: Addr,
+    buyer: Addr,
+    price: Uint128,
+    denom: Denom
+) -> Result<(), ContractError> {
+    let id = TRADE_COUNT.may_load(storage)?.unwrap_or(0) + 1;
+    TRADE_COUNT.save(storage, &id)?;
+
+    let trade = Trade {
+        id,
+        token_ids: token_ids.clone(),
+        seller,
+        buyer,
+        price,
+        denom: denom.clone(),
+        time
+    };
+    TRADES.save(storage, id, &trade)?;
+    // a bundle token's last sale is the whole bundle trade
+    for token_id in token_ids {
+        LAST_SALES.save(storage, &token_id.to_string(), &trade)?;
+    }
+
+    let key = denom_key(denom.clone());
+    let mut volume = VOLUMES.may_load(storage, &key)?.unwrap_or(Volume {
+        denom,
+        amount: Uint128::zero(),
+        trade_count: 0
+    });
+    volume.amount += price;
+    volume.trade_count += 1;
+    VOLUMES.save(storage, &key, &volume)?;
+    Ok(())
+}
+
 pub fn transfer_token_message(
     denom: Denom,
     amount: Uint128,
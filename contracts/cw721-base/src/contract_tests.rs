@@ -1,14 +1,20 @@
 #![cfg(test)]
 use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-use cosmwasm_std::{from_binary, to_binary, CosmosMsg, DepsMut, Empty, Response, WasmMsg};
+use cosmwasm_std::{
+    from_binary, to_binary, Addr, CosmosMsg, DepsMut, Empty, Response, Uint128, WasmMsg,
+};
 
 use cw721::{
     Approval, ApprovalResponse, ContractInfoResponse, Cw721Query, Cw721ReceiveMsg, Expiration,
     NftInfoResponse, OperatorsResponse, OwnerOfResponse,
 };
 
+use crate::msg::{
+    EditMsg, MetadataFrozenResponse, RoyaltiesInfoResponse, RoyaltyPayment, RoyaltyPaymentsResponse,
+};
 use crate::{
-    ContractError, Cw721Contract, ExecuteMsg, Extension, InstantiateMsg, MintMsg, QueryMsg,
+    ContractError, Cw721Contract, ExecuteMsg, Extension, InstantiateMsg, Metadata, MintMsg,
+    QueryMsg, Royalty,
 };
 
 const MINTER: &str = "merlin";
@@ -21,6 +27,8 @@ fn setup_contract(deps: DepsMut<'_>) -> Cw721Contract<'static, Extension, Empty>
         name: CONTRACT_NAME.to_string(),
         symbol: SYMBOL.to_string(),
         minter: String::from(MINTER),
+        royalties: None,
+        placeholder_uri: None,
     };
     let info = mock_info("creator", &[]);
     let res = contract.instantiate(deps, mock_env(), info, msg).unwrap();
@@ -37,6 +45,8 @@ fn proper_instantiation() {
         name: CONTRACT_NAME.to_string(),
         symbol: SYMBOL.to_string(),
         minter: String::from(MINTER),
+        royalties: None,
+        placeholder_uri: None,
     };
     let info = mock_info("creator", &[]);
 
@@ -729,3 +739,401 @@ fn query_tokens_by_owner() {
         .unwrap();
     assert_eq!(&by_demeter[1..], &tokens.tokens[..]);
 }
+
+#[test]
+fn royalty_info() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+
+    // token without royalties in its extension
+    let plain_mint = ExecuteMsg::Mint(MintMsg::<Extension> {
+        token_id: "plain".to_string(),
+        owner: String::from("medusa"),
+        token_uri: None,
+        extension: None,
+    });
+    // token paying its own royalties, rates are in millionths
+    let royalty_mint = ExecuteMsg::Mint(MintMsg::<Extension> {
+        token_id: "royal".to_string(),
+        owner: String::from("medusa"),
+        token_uri: None,
+        extension: Some(Metadata {
+            royalties: Some(vec![
+                Royalty {
+                    address: Addr::unchecked("artist"),
+                    rate: 50_000,
+                },
+                Royalty {
+                    address: Addr::unchecked("studio"),
+                    rate: 25_000,
+                },
+            ]),
+            ..Metadata::default()
+        }),
+    });
+    let allowed = mock_info(MINTER, &[]);
+    contract
+        .execute(deps.as_mut(), mock_env(), allowed.clone(), plain_mint)
+        .unwrap();
+    let res = contract.check_royalties(deps.as_ref()).unwrap();
+    assert!(!res.royalty_payments);
+    contract
+        .execute(deps.as_mut(), mock_env(), allowed.clone(), royalty_mint)
+        .unwrap();
+    let res = contract.check_royalties(deps.as_ref()).unwrap();
+    assert!(res.royalty_payments);
+
+    // nothing to pay without default royalties
+    let res = contract
+        .royalty_payments(deps.as_ref(), "plain".to_string(), Uint128::new(1000))
+        .unwrap();
+    assert_eq!(res, RoyaltyPaymentsResponse { payments: vec![] });
+    let res = contract
+        .royalty_info(deps.as_ref(), "plain".to_string(), Uint128::new(1000))
+        .unwrap();
+    assert_eq!(
+        res,
+        RoyaltiesInfoResponse {
+            address: String::new(),
+            royalty_amount: Uint128::zero(),
+        }
+    );
+
+    // token royalties are used when set
+    let res = contract
+        .royalty_payments(deps.as_ref(), "royal".to_string(), Uint128::new(1000))
+        .unwrap();
+    assert_eq!(
+        res,
+        RoyaltyPaymentsResponse {
+            payments: vec![
+                RoyaltyPayment {
+                    address: String::from("artist"),
+                    amount: Uint128::new(50),
+                },
+                RoyaltyPayment {
+                    address: String::from("studio"),
+                    amount: Uint128::new(25),
+                },
+            ],
+        }
+    );
+
+    // the CW-2981 answer names the first recipient with its own share
+    let res = contract
+        .royalty_info(deps.as_ref(), "royal".to_string(), Uint128::new(1000))
+        .unwrap();
+    assert_eq!(
+        res,
+        RoyaltiesInfoResponse {
+            address: String::from("artist"),
+            royalty_amount: Uint128::new(50),
+        }
+    );
+
+    // only the minter can set the default royalties
+    let update_msg = ExecuteMsg::UpdateDefaultRoyalties {
+        royalties: Some(vec![Royalty {
+            address: Addr::unchecked("collection"),
+            rate: 100_000,
+        }]),
+    };
+    let random = mock_info("random", &[]);
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), random, update_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    contract
+        .execute(deps.as_mut(), mock_env(), allowed, update_msg)
+        .unwrap();
+
+    // tokens without royalties fall back to the default
+    let res = contract
+        .royalty_payments(deps.as_ref(), "plain".to_string(), Uint128::new(1000))
+        .unwrap();
+    assert_eq!(
+        res.payments,
+        vec![RoyaltyPayment {
+            address: String::from("collection"),
+            amount: Uint128::new(100),
+        }]
+    );
+
+    // token royalties still take precedence
+    let res = contract
+        .royalty_payments(deps.as_ref(), "royal".to_string(), Uint128::new(1000))
+        .unwrap();
+    assert_eq!(res.payments.len(), 2);
+
+    // same answer through the query entry point
+    let res: RoyaltyPaymentsResponse = from_binary(
+        &contract
+            .query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::RoyaltyPayments {
+                    token_id: "plain".to_string(),
+                    sale_price: Uint128::new(1000),
+                },
+            )
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.payments[0].amount, Uint128::new(100));
+    let res: RoyaltiesInfoResponse = from_binary(
+        &contract
+            .query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::RoyaltyInfo {
+                    token_id: "plain".to_string(),
+                    sale_price: Uint128::new(1000),
+                },
+            )
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.address, "collection");
+    assert_eq!(res.royalty_amount, Uint128::new(100));
+
+    // burning the only token with royalties leaves the default ones
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("medusa", &[]),
+            ExecuteMsg::Burn {
+                token_id: "royal".to_string(),
+            },
+        )
+        .unwrap();
+    let res = contract.check_royalties(deps.as_ref()).unwrap();
+    assert!(res.royalty_payments);
+    assert_eq!(contract.royalty_tokens.load(&deps.storage).unwrap(), 0);
+}
+
+#[test]
+fn reveal() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty>::default();
+    let placeholder = "ipfs://placeholder.json".to_string();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: String::from(MINTER),
+        royalties: None,
+        placeholder_uri: Some(placeholder.clone()),
+    };
+    contract
+        .instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg)
+        .unwrap();
+
+    let allowed = mock_info(MINTER, &[]);
+    let minted = [
+        ("1", Some(placeholder.clone())),
+        ("2", None),
+        ("3", Some("ipfs://own.json".to_string())),
+    ];
+    for (token_id, token_uri) in minted {
+        let mint_msg = ExecuteMsg::Mint(MintMsg::<Extension> {
+            token_id: token_id.to_string(),
+            owner: String::from("medusa"),
+            token_uri,
+            extension: None,
+        });
+        contract
+            .execute(deps.as_mut(), mock_env(), allowed.clone(), mint_msg)
+            .unwrap();
+    }
+
+    // tokens without a uri of their own show the placeholder before the reveal
+    let info = contract.nft_info(deps.as_ref(), "1".to_string()).unwrap();
+    assert_eq!(info.token_uri, Some(placeholder.clone()));
+    let info = contract.nft_info(deps.as_ref(), "2".to_string()).unwrap();
+    assert_eq!(info.token_uri, Some(placeholder));
+
+    // only the minter can reveal
+    let reveal_msg = ExecuteMsg::Reveal {
+        base_uri: "ipfs://revealed/".to_string(),
+    };
+    let random = mock_info("random", &[]);
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), random, reveal_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    contract
+        .execute(deps.as_mut(), mock_env(), allowed.clone(), reveal_msg.clone())
+        .unwrap();
+
+    // token uris now point into the base uri
+    let info = contract.nft_info(deps.as_ref(), "1".to_string()).unwrap();
+    assert_eq!(info.token_uri, Some("ipfs://revealed/1".to_string()));
+    let info = contract
+        .all_nft_info(deps.as_ref(), mock_env(), "2".to_string(), false)
+        .unwrap();
+    assert_eq!(info.info.token_uri, Some("ipfs://revealed/2".to_string()));
+
+    // a stored uri wins over the base uri
+    let info = contract.nft_info(deps.as_ref(), "3".to_string()).unwrap();
+    assert_eq!(info.token_uri, Some("ipfs://own.json".to_string()));
+
+    // the reveal can not be redone
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), allowed, reveal_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::AlreadyRevealed {});
+}
+
+#[test]
+fn reveal_after_freeze() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+
+    let allowed = mock_info(MINTER, &[]);
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            allowed.clone(),
+            ExecuteMsg::FreezeMetadata { token_id: None },
+        )
+        .unwrap();
+
+    // frozen metadata can not be revealed anymore
+    let reveal_msg = ExecuteMsg::Reveal {
+        base_uri: "ipfs://revealed/".to_string(),
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), allowed, reveal_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::MetadataFrozen {});
+}
+
+#[test]
+fn editing_and_freezing_metadata() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+
+    let allowed = mock_info(MINTER, &[]);
+    for token_id in ["typo", "dynamic"] {
+        let mint_msg = ExecuteMsg::Mint(MintMsg::<Extension> {
+            token_id: token_id.to_string(),
+            owner: String::from("medusa"),
+            token_uri: Some("ipfs://tpyo.json".to_string()),
+            extension: None,
+        });
+        contract
+            .execute(deps.as_mut(), mock_env(), allowed.clone(), mint_msg)
+            .unwrap();
+    }
+
+    let edit_msg = |token_id: &str| {
+        ExecuteMsg::Edit(EditMsg::<Extension> {
+            token_id: token_id.to_string(),
+            token_uri: Some("ipfs://typo.json".to_string()),
+            extension: Some(Some(Metadata {
+                name: Some("Fixed".to_string()),
+                ..Metadata::default()
+            })),
+        })
+    };
+
+    // random cannot edit
+    let random = mock_info("random", &[]);
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), random.clone(), edit_msg("typo"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // minter can edit
+    contract
+        .execute(deps.as_mut(), mock_env(), allowed.clone(), edit_msg("typo"))
+        .unwrap();
+    let info = contract.nft_info(deps.as_ref(), "typo".to_string()).unwrap();
+    assert_eq!(info.token_uri, Some("ipfs://typo.json".to_string()));
+    assert_eq!(info.extension.unwrap().name, Some("Fixed".to_string()));
+
+    // editing only the uri keeps the current metadata
+    let uri_msg = ExecuteMsg::Edit(EditMsg::<Extension> {
+        token_id: "typo".to_string(),
+        token_uri: Some("ipfs://fixed.json".to_string()),
+        extension: None,
+    });
+    contract
+        .execute(deps.as_mut(), mock_env(), allowed.clone(), uri_msg)
+        .unwrap();
+    let info = contract.nft_info(deps.as_ref(), "typo".to_string()).unwrap();
+    assert_eq!(info.token_uri, Some("ipfs://fixed.json".to_string()));
+    assert_eq!(info.extension.unwrap().name, Some("Fixed".to_string()));
+
+    // editing only the metadata keeps the current uri
+    let metadata_msg = ExecuteMsg::Edit(EditMsg::<Extension> {
+        token_id: "typo".to_string(),
+        token_uri: None,
+        extension: Some(Some(Metadata {
+            name: Some("Final".to_string()),
+            ..Metadata::default()
+        })),
+    });
+    contract
+        .execute(deps.as_mut(), mock_env(), allowed.clone(), metadata_msg)
+        .unwrap();
+    let info = contract.nft_info(deps.as_ref(), "typo".to_string()).unwrap();
+    assert_eq!(info.token_uri, Some("ipfs://fixed.json".to_string()));
+    assert_eq!(info.extension.unwrap().name, Some("Final".to_string()));
+
+    // only the minter can freeze
+    let freeze_msg = ExecuteMsg::FreezeMetadata {
+        token_id: Some("typo".to_string()),
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), random, freeze_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    contract
+        .execute(deps.as_mut(), mock_env(), allowed.clone(), freeze_msg)
+        .unwrap();
+
+    // frozen token can no longer be edited, the others still can
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), allowed.clone(), edit_msg("typo"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::MetadataFrozen {});
+    contract
+        .execute(deps.as_mut(), mock_env(), allowed.clone(), edit_msg("dynamic"))
+        .unwrap();
+    let res = contract
+        .metadata_frozen(deps.as_ref(), Some("typo".to_string()))
+        .unwrap();
+    assert!(res.frozen);
+    let res = contract.metadata_frozen(deps.as_ref(), None).unwrap();
+    assert!(!res.frozen);
+
+    // freezing the contract freezes every token
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            allowed.clone(),
+            ExecuteMsg::FreezeMetadata { token_id: None },
+        )
+        .unwrap();
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), allowed, edit_msg("dynamic"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::MetadataFrozen {});
+
+    let res: MetadataFrozenResponse = from_binary(
+        &contract
+            .query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::MetadataFrozen {
+                    token_id: Some("dynamic".to_string()),
+                },
+            )
+            .unwrap(),
+    )
+    .unwrap();
+    assert!(res.frozen);
+}
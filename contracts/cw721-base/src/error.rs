@@ -20,4 +20,10 @@ pub enum ContractError {
 
     #[error("Approval not found for: {spender}")]
     ApprovalNotFound { spender: String },
+
+    #[error("Token metadata is frozen")]
+    MetadataFrozen {},
+
+    #[error("Token metadata is already revealed")]
+    AlreadyRevealed {},
 }
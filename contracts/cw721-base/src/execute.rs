@@ -9,7 +9,9 @@ use cw721::{ContractInfoResponse, CustomMsg, Cw721Execute, Cw721ReceiveMsg, Expi
 
 use crate::error::ContractError;
 use crate::msg::{ExecuteMsg, InstantiateMsg, MintMsg, BatchMintMsg, EditMsg};
+use crate::query::has_royalties;
 use crate::state::{Approval, Cw721Contract, TokenInfo};
+use crate::{Royalty, RoyaltyExtension};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw721-base";
@@ -18,7 +20,7 @@ const ONLYOWNER:bool = false;
 
 impl<'a, T, C> Cw721Contract<'a, T, C>
 where
-    T: Serialize + DeserializeOwned + Clone,
+    T: Serialize + DeserializeOwned + Clone + RoyaltyExtension,
     C: CustomMsg,
 {
     pub fn instantiate(
@@ -37,6 +39,12 @@ where
         self.contract_info.save(deps.storage, &info)?;
         let minter = deps.api.addr_validate(&msg.minter)?;
         self.minter.save(deps.storage, &minter)?;
+        if let Some(royalties) = msg.royalties {
+            self.default_royalties.save(deps.storage, &royalties)?;
+        }
+        if let Some(placeholder_uri) = msg.placeholder_uri {
+            self.placeholder_uri.save(deps.storage, &placeholder_uri)?;
+        }
         Ok(Response::default())
     }
 
@@ -51,7 +59,16 @@ where
             ExecuteMsg::ChangeMinter{new_minter} => {
                 self.change_minter(deps, info, new_minter)
             },
-            // ExecuteMsg::Edit(msg) => self.edit(deps, env, info, msg),
+            ExecuteMsg::UpdateDefaultRoyalties{royalties} => {
+                self.update_default_royalties(deps, info, royalties)
+            },
+            ExecuteMsg::Reveal{base_uri} => {
+                self.reveal(deps, info, base_uri)
+            },
+            ExecuteMsg::Edit(msg) => self.edit(deps, env, info, msg),
+            ExecuteMsg::FreezeMetadata { token_id } => {
+                self.freeze_metadata(deps, info, token_id)
+            }
             ExecuteMsg::Mint(msg) => self.mint(deps, env, info, msg),
             ExecuteMsg::BatchMint(msg) => self.batch_mint(deps, env, info, msg),
             ExecuteMsg::Approve {
@@ -83,7 +100,7 @@ where
 // TODO pull this into some sort of trait extension??
 impl<'a, T, C> Cw721Contract<'a, T, C>
 where
-    T: Serialize + DeserializeOwned + Clone,
+    T: Serialize + DeserializeOwned + Clone + RoyaltyExtension,
     C: CustomMsg,
 {
 
@@ -106,33 +123,109 @@ where
 
     }
 
+    pub fn update_default_royalties(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        royalties: Option<Vec<Royalty>>
+    ) -> Result<Response<C>, ContractError> {
+        let minter = self.minter.load(deps.storage)?;
+
+        if info.sender != minter {
+            return Err(ContractError::Unauthorized {});
+        }
+        match royalties {
+            Some(royalties) => self.default_royalties.save(deps.storage, &royalties)?,
+            None => self.default_royalties.remove(deps.storage)
+        }
+        Ok(Response::new()
+            .add_attribute("action", "update_default_royalties"))
+    }
+
+    pub fn reveal(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        base_uri: String
+    ) -> Result<Response<C>, ContractError> {
+        let minter = self.minter.load(deps.storage)?;
+
+        if info.sender != minter {
+            return Err(ContractError::Unauthorized {});
+        }
+        if self.base_uri.may_load(deps.storage)?.is_some() {
+            return Err(ContractError::AlreadyRevealed {});
+        }
+        if self.all_frozen.may_load(deps.storage)?.unwrap_or(false) {
+            return Err(ContractError::MetadataFrozen {});
+        }
+        self.base_uri.save(deps.storage, &base_uri)?;
+        Ok(Response::new()
+            .add_attribute("action", "reveal")
+            .add_attribute("base_uri", base_uri))
+    }
+
     
-    // pub fn edit(
-    //     &self,
-    //     deps: DepsMut,
-    //     _env: Env,
-    //     info: MessageInfo,
-    //     msg: EditMsg<T>,
-    // ) -> Result<Response<C>, ContractError> {
-        
-    //     let mut minter = self.minter.load(deps.storage)?;
-        
-    //     if info.sender != minter {
-    //         return Err(ContractError::Unauthorized {});
-    //     }
-    //     let mut token = self.tokens.load(deps.storage, &msg.token_id)?;
-        
-    //     token.extension = msg.extension;
-    //     token.token_uri = msg.token_uri;
+    pub fn edit(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        msg: EditMsg<T>,
+    ) -> Result<Response<C>, ContractError> {
+        let minter = self.minter.load(deps.storage)?;
+
+        if info.sender != minter {
+            return Err(ContractError::Unauthorized {});
+        }
+        if self.is_frozen(deps.storage, &msg.token_id)? {
+            return Err(ContractError::MetadataFrozen {});
+        }
+        let mut token = self.tokens.load(deps.storage, &msg.token_id)?;
+        if let Some(extension) = msg.extension {
+            self.update_royalty_tokens(
+                deps.storage,
+                has_royalties(&token.extension),
+                has_royalties(&extension),
+            )?;
+            token.extension = extension;
+        }
+        if msg.token_uri.is_some() {
+            token.token_uri = msg.token_uri;
+        }
+
+        self.tokens.save(deps.storage, &msg.token_id, &token)?;
 
-    //     self.tokens.save(deps.storage, &msg.token_id, &token)?;
+        Ok(Response::new()
+            .add_attribute("action", "edit")
+            .add_attribute("editor", info.sender)
+            .add_attribute("token_id", msg.token_id))
+    }
+
+    pub fn freeze_metadata(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        token_id: Option<String>,
+    ) -> Result<Response<C>, ContractError> {
+        let minter = self.minter.load(deps.storage)?;
 
+        if info.sender != minter {
+            return Err(ContractError::Unauthorized {});
+        }
+        match token_id.clone() {
+            Some(token_id) => {
+                // only existing tokens can be frozen
+                self.tokens.load(deps.storage, &token_id)?;
+                self.frozen_tokens.save(deps.storage, &token_id, &true)?;
+            }
+            None => self.all_frozen.save(deps.storage, &true)?,
+        }
 
-    //     Ok(Response::new()
-    //         .add_attribute("action", "edit")
-    //         .add_attribute("editor", info.sender.clone())
-    //         .add_attribute("token_id", msg.token_id))
-    // }
+        Ok(Response::new()
+            .add_attribute("action", "freeze_metadata")
+            .add_attribute("token_id", token_id.unwrap_or_else(|| "all".to_string())))
+    }
 
     pub fn mint(
         &self,
@@ -154,11 +247,13 @@ where
             token_uri: msg.token_uri,
             extension: msg.extension,
         };
+        let royalties = has_royalties(&token.extension);
         self.tokens
             .update(deps.storage, &msg.token_id, |old| match old {
                 Some(_) => Err(ContractError::Claimed {}),
                 None => Ok(token),
             })?;
+        self.update_royalty_tokens(deps.storage, false, royalties)?;
 
         self.increment_tokens(deps.storage)?;
 
@@ -192,11 +287,13 @@ where
                 token_uri: Some(msg.token_uri[i].clone()),
                 extension: msg.extension[i].clone(),
             };
+            let royalties = has_royalties(&token.extension);
             self.tokens
                 .update(deps.storage, &msg.token_id[i], |old| match old {
                     Some(_) => Err(ContractError::Claimed {}),
                     None => Ok(token),
                 })?;
+            self.update_royalty_tokens(deps.storage, false, royalties)?;
     
             self.increment_tokens(deps.storage)?;
         }
@@ -211,7 +308,7 @@ where
 
 impl<'a, T, C> Cw721Execute<T, C> for Cw721Contract<'a, T, C>
 where
-    T: Serialize + DeserializeOwned + Clone,
+    T: Serialize + DeserializeOwned + Clone + RoyaltyExtension,
     C: CustomMsg,
 {
     type Err = ContractError;
@@ -399,6 +496,7 @@ where
 
         self.tokens.remove(deps.storage, &token_id)?;
         self.decrement_tokens(deps.storage)?;
+        self.update_royalty_tokens(deps.storage, has_royalties(&token.extension), false)?;
 
         Ok(Response::new()
             .add_attribute("action", "burn")
@@ -410,7 +508,7 @@ where
 // helpers
 impl<'a, T, C> Cw721Contract<'a, T, C>
 where
-    T: Serialize + DeserializeOwned + Clone,
+    T: Serialize + DeserializeOwned + Clone + RoyaltyExtension,
     C: CustomMsg,
 {
     pub fn _transfer_nft(
@@ -1,5 +1,10 @@
+use crate::msg::{
+    CheckRoyaltiesResponse, MetadataFrozenResponse, RoyaltiesInfoResponse, RoyaltyPaymentsResponse,
+};
 use crate::{ExecuteMsg, QueryMsg};
-use cosmwasm_std::{to_binary, Addr, CosmosMsg, QuerierWrapper, StdResult, WasmMsg, WasmQuery};
+use cosmwasm_std::{
+    to_binary, Addr, CosmosMsg, QuerierWrapper, StdResult, Uint128, WasmMsg, WasmQuery,
+};
 use cw721::{
     AllNftInfoResponse, Approval, ApprovalResponse, ApprovalsResponse, ContractInfoResponse,
     NftInfoResponse, NumTokensResponse, OperatorsResponse, OwnerOfResponse, TokensResponse,
@@ -140,6 +145,52 @@ impl Cw721Contract {
         self.query(querier, req)
     }
 
+    /// CW-2981 royalty on a sale of the token, first recipient only
+    pub fn royalty_info<T: Into<String>>(
+        &self,
+        querier: &QuerierWrapper,
+        token_id: T,
+        sale_price: Uint128,
+    ) -> StdResult<RoyaltiesInfoResponse> {
+        let req = QueryMsg::RoyaltyInfo {
+            token_id: token_id.into(),
+            sale_price,
+        };
+        self.query(querier, req)
+    }
+
+    /// Royalty payments on a sale of the token, one per recipient
+    pub fn royalty_payments<T: Into<String>>(
+        &self,
+        querier: &QuerierWrapper,
+        token_id: T,
+        sale_price: Uint128,
+    ) -> StdResult<RoyaltyPaymentsResponse> {
+        let req = QueryMsg::RoyaltyPayments {
+            token_id: token_id.into(),
+            sale_price,
+        };
+        self.query(querier, req)
+    }
+
+    /// returns true if the contract pays royalties
+    pub fn check_royalties(&self, querier: &QuerierWrapper) -> StdResult<bool> {
+        let req = QueryMsg::CheckRoyalties {};
+        let res: CheckRoyaltiesResponse = self.query(querier, req)?;
+        Ok(res.royalty_payments)
+    }
+
+    /// returns true if the metadata of the token, or of every token, is frozen
+    pub fn metadata_frozen(
+        &self,
+        querier: &QuerierWrapper,
+        token_id: Option<String>,
+    ) -> StdResult<bool> {
+        let req = QueryMsg::MetadataFrozen { token_id };
+        let res: MetadataFrozenResponse = self.query(querier, req)?;
+        Ok(res.frozen)
+    }
+
     /// With enumerable extension
     pub fn tokens<T: Into<String>>(
         &self,
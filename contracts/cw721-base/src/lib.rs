@@ -10,7 +10,7 @@ use schemars::JsonSchema;
 pub use crate::error::ContractError;
 pub use crate::msg::{ExecuteMsg, InstantiateMsg, MintMsg, MinterResponse, QueryMsg};
 pub use crate::state::Cw721Contract;
-use cosmwasm_std::Empty;
+use cosmwasm_std::{Addr, Empty};
 use serde::{Deserialize, Serialize};
 
 
@@ -21,6 +21,13 @@ pub struct Trait {
     pub value: String,
 }
 
+// rate is in millionths of the sale price
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Royalty {
+    pub address: Addr,
+    pub rate: u32,
+}
+
 // see: https://docs.opensea.io/docs/metadata-standards
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
 pub struct Metadata {
@@ -33,10 +40,30 @@ pub struct Metadata {
     pub background_color: Option<String>,
     pub animation_url: Option<String>,
     pub youtube_url: Option<String>,
-    pub timestamp: Option<u64>
+    pub timestamp: Option<u64>,
+    /// Overrides the royalties of the collection for this token
+    pub royalties: Option<Vec<Royalty>>
 }
 
 pub type Extension = Option<Metadata>;
+
+/// Extensions that can carry royalties of their own, they take precedence
+/// over the default royalties of the contract
+pub trait RoyaltyExtension {
+    fn royalties(&self) -> Option<Vec<Royalty>>;
+}
+
+impl RoyaltyExtension for Extension {
+    fn royalties(&self) -> Option<Vec<Royalty>> {
+        self.as_ref().and_then(|metadata| metadata.royalties.clone())
+    }
+}
+
+impl RoyaltyExtension for Empty {
+    fn royalties(&self) -> Option<Vec<Royalty>> {
+        None
+    }
+}
 pub type Cw721MetadataContract<'a> = Cw721Contract<'a, Extension, Empty>;
 // pub type ExecuteMsg = ExecuteMsg<Extension>;
 
@@ -3,7 +3,8 @@ use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::Binary;
 use cw721::Expiration;
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, Uint128};
+use crate::Royalty;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
@@ -16,6 +17,11 @@ pub struct InstantiateMsg {
     /// This is designed for a base NFT that is controlled by an external program
     /// or contract. You will likely replace this with custom logic in custom NFTs
     pub minter: String,
+
+    /// Royalties of the tokens that do not set their own
+    pub royalties: Option<Vec<Royalty>>,
+    /// Served until the reveal for tokens minted without a token_uri or with the placeholder
+    pub placeholder_uri: Option<String>,
 }
 
 /// This is like Cw721ExecuteMsg but we add a Mint command for an owner
@@ -53,11 +59,27 @@ pub enum ExecuteMsg<T> {
 
     /// Mint a new NFT, can only be called by the contract minter
     Mint(MintMsg<T>),
-    // Edit(EditMsg<T>),
+    /// Replace the token_uri and the extension of an NFT, each only when given.
+    /// Can only be called by the minter
+    Edit(EditMsg<T>),
     BatchMint(BatchMintMsg<T>),
     ChangeMinter {
         new_minter: Addr
     },
+    /// Replace the contract level royalties, can only be called by the minter
+    UpdateDefaultRoyalties {
+        royalties: Option<Vec<Royalty>>
+    },
+    /// Reject any further Edit of the token, or of every token when token_id is None.
+    /// Can only be called by the minter and can not be undone
+    FreezeMetadata {
+        token_id: Option<String>
+    },
+    /// Serve tokens without a token_uri of their own as base_uri/{token_id}.
+    /// Can only be called once by the minter, and not after every token is frozen
+    Reveal {
+        base_uri: String
+    },
 
     /// Burn an NFT the sender has access to
     Burn { token_id: String },
@@ -65,10 +87,10 @@ pub enum ExecuteMsg<T> {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct EditMsg<T> {
     pub token_id: String,
-    /// Metadata JSON Schema
+    /// Metadata JSON Schema, None keeps the current token_uri
     pub token_uri: Option<String>,
-    /// Any custom extension used by this contract
-    pub extension: T,
+    /// Any custom extension used by this contract, None keeps the current extension
+    pub extension: Option<T>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -173,6 +195,29 @@ pub enum QueryMsg {
 
     // Return the minter
     Minter {},
+
+    /// CW-2981 royalty of a sale: the first royalty recipient and its share.
+    /// Tokens with several recipients list all of them through RoyaltyPayments.
+    /// Return type: RoyaltiesInfoResponse
+    RoyaltyInfo {
+        token_id: String,
+        sale_price: Uint128,
+    },
+    /// Royalty payments owed on a sale, one per recipient. Uses the royalties of
+    /// the token extension, falling back to the contract level royalties.
+    /// Return type: RoyaltyPaymentsResponse
+    RoyaltyPayments {
+        token_id: String,
+        sale_price: Uint128,
+    },
+    /// Whether the contract or any of its tokens sets royalties
+    /// Return type: CheckRoyaltiesResponse
+    CheckRoyalties {},
+    /// Whether the metadata of the token, or of every token when token_id is None, is frozen
+    /// Return type: MetadataFrozenResponse
+    MetadataFrozen {
+        token_id: Option<String>,
+    },
 }
 
 /// Shows who can mint these tokens
@@ -180,3 +225,32 @@ pub enum QueryMsg {
 pub struct MinterResponse {
     pub minter: String,
 }
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RoyaltyPayment {
+    pub address: String,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RoyaltiesInfoResponse {
+    /// Empty when the sale pays no royalties
+    pub address: String,
+    pub royalty_amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RoyaltyPaymentsResponse {
+    /// Payment of every recipient
+    pub payments: Vec<RoyaltyPayment>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct CheckRoyaltiesResponse {
+    pub royalty_payments: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct MetadataFrozenResponse {
+    pub frozen: bool,
+}
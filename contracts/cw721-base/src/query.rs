@@ -1,7 +1,9 @@
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
-use cosmwasm_std::{to_binary, Addr, Binary, BlockInfo, Deps, Env, Order, StdError, StdResult};
+use cosmwasm_std::{
+    to_binary, Addr, Binary, BlockInfo, Deps, Env, Order, StdError, StdResult, Uint128,
+};
 
 use cw721::{
     AllNftInfoResponse, ApprovalResponse, ApprovalsResponse, ContractInfoResponse, CustomMsg,
@@ -11,11 +13,17 @@ use cw721::{
 use cw_storage_plus::Bound;
 use cw_utils::maybe_addr;
 
-use crate::msg::{MinterResponse, QueryMsg};
+use crate::msg::{
+    CheckRoyaltiesResponse, MetadataFrozenResponse, MinterResponse, QueryMsg,
+    RoyaltiesInfoResponse, RoyaltyPayment, RoyaltyPaymentsResponse,
+};
 use crate::state::{Approval, Cw721Contract, TokenInfo};
+use crate::RoyaltyExtension;
 
 const DEFAULT_LIMIT: u32 = 10;
 const MAX_LIMIT: u32 = 30;
+// royalty rates are in millionths
+const ROYALTY_DENOMINATOR: u128 = 1_000_000;
 
 impl<'a, T, C> Cw721Query<T> for Cw721Contract<'a, T, C>
 where
@@ -34,7 +42,7 @@ where
     fn nft_info(&self, deps: Deps, token_id: String) -> StdResult<NftInfoResponse<T>> {
         let info = self.tokens.load(deps.storage, &token_id)?;
         Ok(NftInfoResponse {
-            token_uri: info.token_uri,
+            token_uri: self.token_uri(deps.storage, &token_id, info.token_uri)?,
             extension: info.extension,
         })
     }
@@ -189,7 +197,7 @@ where
                 approvals: humanize_approvals(&env.block, &info, include_expired),
             },
             info: NftInfoResponse {
-                token_uri: info.token_uri,
+                token_uri: self.token_uri(deps.storage, &token_id, info.token_uri)?,
                 extension: info.extension,
             },
         })
@@ -198,7 +206,7 @@ where
 
 impl<'a, T, C> Cw721Contract<'a, T, C>
 where
-    T: Serialize + DeserializeOwned + Clone,
+    T: Serialize + DeserializeOwned + Clone + RoyaltyExtension,
     C: CustomMsg,
 {
     pub fn minter(&self, deps: Deps) -> StdResult<MinterResponse> {
@@ -208,9 +216,89 @@ where
         })
     }
 
+    pub fn royalty_info(
+        &self,
+        deps: Deps,
+        token_id: String,
+        sale_price: Uint128,
+    ) -> StdResult<RoyaltiesInfoResponse> {
+        let payments = self.royalty_payments(deps, token_id, sale_price)?.payments;
+        Ok(match payments.into_iter().next() {
+            Some(payment) => RoyaltiesInfoResponse {
+                address: payment.address,
+                royalty_amount: payment.amount,
+            },
+            None => RoyaltiesInfoResponse {
+                address: String::new(),
+                royalty_amount: Uint128::zero(),
+            },
+        })
+    }
+
+    pub fn royalty_payments(
+        &self,
+        deps: Deps,
+        token_id: String,
+        sale_price: Uint128,
+    ) -> StdResult<RoyaltyPaymentsResponse> {
+        let info = self.tokens.load(deps.storage, &token_id)?;
+        let royalties = match info.extension.royalties() {
+            Some(royalties) => royalties,
+            None => self
+                .default_royalties
+                .may_load(deps.storage)?
+                .unwrap_or_default(),
+        };
+
+        let payments: Vec<RoyaltyPayment> = royalties
+            .iter()
+            .map(|royalty| RoyaltyPayment {
+                address: royalty.address.to_string(),
+                amount: sale_price.multiply_ratio(royalty.rate, ROYALTY_DENOMINATOR),
+            })
+            .collect();
+
+        Ok(RoyaltyPaymentsResponse { payments })
+    }
+
+    pub fn check_royalties(&self, deps: Deps) -> StdResult<CheckRoyaltiesResponse> {
+        let default_royalties = self
+            .default_royalties
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        let royalty_tokens = self.royalty_tokens.may_load(deps.storage)?.unwrap_or_default();
+        Ok(CheckRoyaltiesResponse {
+            royalty_payments: !default_royalties.is_empty() || royalty_tokens > 0,
+        })
+    }
+
+    pub fn metadata_frozen(
+        &self,
+        deps: Deps,
+        token_id: Option<String>,
+    ) -> StdResult<MetadataFrozenResponse> {
+        let frozen = match token_id {
+            Some(token_id) => self.is_frozen(deps.storage, &token_id)?,
+            None => self.all_frozen.may_load(deps.storage)?.unwrap_or(false),
+        };
+        Ok(MetadataFrozenResponse { frozen })
+    }
+
     pub fn query(&self, deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         match msg {
             QueryMsg::Minter {} => to_binary(&self.minter(deps)?),
+            QueryMsg::RoyaltyInfo {
+                token_id,
+                sale_price,
+            } => to_binary(&self.royalty_info(deps, token_id, sale_price)?),
+            QueryMsg::RoyaltyPayments {
+                token_id,
+                sale_price,
+            } => to_binary(&self.royalty_payments(deps, token_id, sale_price)?),
+            QueryMsg::CheckRoyalties {} => to_binary(&self.check_royalties(deps)?),
+            QueryMsg::MetadataFrozen { token_id } => {
+                to_binary(&self.metadata_frozen(deps, token_id)?)
+            }
             QueryMsg::ContractInfo {} => to_binary(&self.contract_info(deps)?),
             QueryMsg::NftInfo { token_id } => to_binary(&self.nft_info(deps, token_id)?),
             QueryMsg::OwnerOf {
@@ -271,6 +359,11 @@ where
     }
 }
 
+/// Whether the extension sets royalties of its own
+pub(crate) fn has_royalties<T: RoyaltyExtension>(extension: &T) -> bool {
+    extension.royalties().map_or(false, |royalties| !royalties.is_empty())
+}
+
 fn parse_approval(item: StdResult<(Addr, Expiration)>) -> StdResult<cw721::Approval> {
     item.map(|(spender, expires)| cw721::Approval {
         spender: spender.to_string(),
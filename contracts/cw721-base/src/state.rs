@@ -8,6 +8,8 @@ use cosmwasm_std::{Addr, BlockInfo, StdResult, Storage};
 use cw721::{ContractInfoResponse, CustomMsg, Cw721, Expiration};
 use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
 
+use crate::{Royalty, RoyaltyExtension};
+
 pub struct Cw721Contract<'a, T, C>
 where
     T: Serialize + DeserializeOwned + Clone,
@@ -15,6 +17,18 @@ where
     pub contract_info: Item<'a, ContractInfoResponse>,
     pub minter: Item<'a, Addr>,
     pub token_count: Item<'a, u64>,
+    /// Royalties of the tokens without royalties in their extension
+    pub default_royalties: Item<'a, Vec<Royalty>>,
+    /// Number of tokens with royalties in their extension
+    pub royalty_tokens: Item<'a, u64>,
+    /// Served until the reveal for tokens minted without a token_uri or with the placeholder
+    pub placeholder_uri: Item<'a, String>,
+    /// Once set, tokens without a token_uri of their own are served as base_uri/{token_id}
+    pub base_uri: Item<'a, String>,
+    /// Set once metadata of every token is frozen for good
+    pub all_frozen: Item<'a, bool>,
+    /// Tokens whose metadata is frozen for good
+    pub frozen_tokens: Map<'a, &'a str, bool>,
     /// Stored as (granter, operator) giving operator full control over granter's account
     pub operators: Map<'a, (&'a Addr, &'a Addr), Expiration>,
     pub tokens: IndexedMap<'a, &'a str, TokenInfo<T>, TokenIndexes<'a, T>>,
@@ -25,7 +39,7 @@ where
 // This is a signal, the implementations are in other files
 impl<'a, T, C> Cw721<T, C> for Cw721Contract<'a, T, C>
 where
-    T: Serialize + DeserializeOwned + Clone,
+    T: Serialize + DeserializeOwned + Clone + RoyaltyExtension,
     C: CustomMsg,
 {
 }
@@ -39,6 +53,12 @@ where
             "nft_info",
             "minter",
             "num_tokens",
+            "default_royalties",
+            "royalty_tokens",
+            "placeholder_uri",
+            "base_uri",
+            "all_frozen",
+            "frozen_tokens",
             "operators",
             "tokens",
             "tokens__owner",
@@ -54,6 +74,12 @@ where
         contract_key: &'a str,
         minter_key: &'a str,
         token_count_key: &'a str,
+        default_royalties_key: &'a str,
+        royalty_tokens_key: &'a str,
+        placeholder_uri_key: &'a str,
+        base_uri_key: &'a str,
+        all_frozen_key: &'a str,
+        frozen_tokens_key: &'a str,
         operator_key: &'a str,
         tokens_key: &'a str,
         tokens_owner_key: &'a str,
@@ -65,6 +91,12 @@ where
             contract_info: Item::new(contract_key),
             minter: Item::new(minter_key),
             token_count: Item::new(token_count_key),
+            default_royalties: Item::new(default_royalties_key),
+            royalty_tokens: Item::new(royalty_tokens_key),
+            placeholder_uri: Item::new(placeholder_uri_key),
+            base_uri: Item::new(base_uri_key),
+            all_frozen: Item::new(all_frozen_key),
+            frozen_tokens: Map::new(frozen_tokens_key),
             operators: Map::new(operator_key),
             tokens: IndexedMap::new(tokens_key, indexes),
             _custom_response: PhantomData,
@@ -81,6 +113,46 @@ where
         Ok(val)
     }
 
+    /// Tracks the tokens paying their own royalties, `had` and `has` tell whether
+    /// the token paid them before and after the change
+    pub fn update_royalty_tokens(
+        &self,
+        storage: &mut dyn Storage,
+        had: bool,
+        has: bool,
+    ) -> StdResult<()> {
+        if had == has {
+            return Ok(());
+        }
+        let count = self.royalty_tokens.may_load(storage)?.unwrap_or_default();
+        let count = if has { count + 1 } else { count.saturating_sub(1) };
+        self.royalty_tokens.save(storage, &count)
+    }
+
+    pub fn token_uri(
+        &self,
+        storage: &dyn Storage,
+        token_id: &str,
+        token_uri: Option<String>,
+    ) -> StdResult<Option<String>> {
+        let placeholder_uri = self.placeholder_uri.may_load(storage)?;
+        // tokens minted without a uri or with the placeholder follow the reveal
+        if token_uri.is_some() && token_uri != placeholder_uri {
+            return Ok(token_uri);
+        }
+        match self.base_uri.may_load(storage)? {
+            Some(base_uri) => Ok(Some(format!("{}/{}", base_uri.trim_end_matches('/'), token_id))),
+            None => Ok(placeholder_uri),
+        }
+    }
+
+    pub fn is_frozen(&self, storage: &dyn Storage, token_id: &str) -> StdResult<bool> {
+        if self.all_frozen.may_load(storage)?.unwrap_or(false) {
+            return Ok(true);
+        }
+        Ok(self.frozen_tokens.may_load(storage, token_id)?.unwrap_or(false))
+    }
+
     pub fn decrement_tokens(&self, storage: &mut dyn Storage) -> StdResult<u64> {
         let val = self.token_count(storage)? - 1;
         self.token_count.save(storage, &val)?;
@@ -10,21 +10,24 @@ use cw_utils::parse_reply_instantiate_data;
 use cw2::{get_contract_version, set_contract_version};
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, Cw20QueryMsg, Cw20CoinVerified};
 use cw20::{TokenInfoResponse, Balance};
-use cw_utils::{maybe_addr};
+use cw_utils::{maybe_addr, Expiration};
 use cw_storage_plus::Bound;
 use crate::error::ContractError;
 use crate::msg::{
-    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, CollectionInfo, CollectionListResponse, CollectionRecord
+    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, CollectionInfo, CollectionListResponse, CollectionRecord,
+    PendingOwner, PendingOwnerResponse
 };
 use crate::state::{
-    Config, CONFIG, COLLECTIONS
+    Config, CONFIG, LEGACY_CONFIG, PENDING_OWNER, COLLECTIONS
 };
 
-use marble_collection::msg::{InstantiateMsg as CollectionInstantiateMsg, ExecuteMsg as CollectionExecuteMsg, QueryMsg as CollectionQueryMsg, ConfigResponse as CollectionConfigResponse};
+use marble_collection::msg::{InstantiateMsg as CollectionInstantiateMsg, ExecuteMsg as CollectionExecuteMsg, QueryMsg as CollectionQueryMsg, ConfigResponse as CollectionConfigResponse, PlatformFee};
 
 // Version info, for migration info
 const CONTRACT_NAME: &str = "marble-marketplace";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+// platform fee is in millionths
+const MULTIPLY: u32 = 1000000u32;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -32,16 +35,23 @@ pub fn instantiate(
     _env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     let owner = info.sender;
 
+    if msg.platform_fee >= MULTIPLY {
+        return Err(ContractError::InvalidPlatformFee {});
+    }
+    let fee_recipient = deps.api.addr_validate(msg.fee_recipient.as_str())?;
+
     let config = Config {
         owner,
         max_collection_id: 0u32,
         collection_code_id: msg.collection_code_id,
         cw721_base_code_id: msg.cw721_base_code_id,
+        platform_fee: msg.platform_fee,
+        fee_recipient,
     };
     CONFIG.save(deps.storage, &config)?;
 
@@ -51,13 +61,16 @@ pub fn instantiate(
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::UpdateConfig { new_owner } => execute_update_config(deps, info, new_owner),
+        ExecuteMsg::UpdateConfig { new_owner, expires } => execute_update_config(deps, info, new_owner, expires),
+        ExecuteMsg::AcceptOwnership {} => execute_accept_ownership(deps, env, info),
+        ExecuteMsg::CancelOwnershipTransfer {} => execute_cancel_ownership_transfer(deps, info),
         ExecuteMsg::UpdateConstants { collection_code_id, cw721_base_code_id } => execute_update_constants(deps, info, collection_code_id, cw721_base_code_id),
+        ExecuteMsg::UpdatePlatformFee { platform_fee, fee_recipient } => execute_update_platform_fee(deps, info, platform_fee, fee_recipient),
         ExecuteMsg::RemoveCollection {id} => execute_remove_collection(deps, info, id),
         ExecuteMsg::RemoveAllCollection {  } => execute_remove_all_collection(deps, info),
         ExecuteMsg::AddCollection(msg) => execute_add_collection(deps, info, msg),
@@ -82,16 +95,58 @@ pub fn execute_update_config(
     deps: DepsMut,
     info: MessageInfo,
     new_owner: Addr,
+    expires: Option<Expiration>
 ) -> Result<Response, ContractError> {
     // authorize owner
     check_owner(&deps, &info)?;
-    
+    // a mistyped owner could never accept
+    let new_owner = deps.api.addr_validate(new_owner.as_str())?;
+
+    PENDING_OWNER.save(deps.storage, &PendingOwner {
+        address: new_owner.clone(),
+        expires
+    })?;
+
+    Ok(Response::new().add_attribute("action", "propose_owner").add_attribute("pending_owner", new_owner))
+}
+
+pub fn execute_accept_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo
+) -> Result<Response, ContractError> {
+    let pending = PENDING_OWNER.may_load(deps.storage)?.ok_or(ContractError::NoPendingOwner {})?;
+    if pending.address != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if let Some(expires) = pending.expires {
+        if expires.is_expired(&env.block) {
+            return Err(ContractError::OwnershipTransferExpired {});
+        }
+    }
+
     CONFIG.update(deps.storage, |mut exists| -> StdResult<_> {
-        exists.owner = new_owner;
+        exists.owner = info.sender.clone();
         Ok(exists)
     })?;
+    PENDING_OWNER.remove(deps.storage);
+
+    Ok(Response::new().add_attribute("action", "accept_owner").add_attribute("owner", info.sender))
+}
+
+pub fn execute_cancel_ownership_transfer(
+    deps: DepsMut,
+    info: MessageInfo
+) -> Result<Response, ContractError> {
+    // authorize owner
+    check_owner(&deps, &info)?;
+
+    if PENDING_OWNER.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::NoPendingOwner {});
+    }
+    PENDING_OWNER.remove(deps.storage);
 
-    Ok(Response::new().add_attribute("action", "update_config"))
+    Ok(Response::new().add_attribute("action", "cancel_owner"))
 }
 
 
@@ -112,17 +167,51 @@ pub fn execute_update_constants(
 
     Ok(Response::new().add_attribute("action", "update_constants"))
 }
+
+// Only applies to the collections added afterwards
+pub fn execute_update_platform_fee(
+    deps: DepsMut,
+    info: MessageInfo,
+    platform_fee: u32,
+    fee_recipient: Addr,
+) -> Result<Response, ContractError> {
+    // authorize owner
+    check_owner(&deps, &info)?;
+
+    if platform_fee >= MULTIPLY {
+        return Err(ContractError::InvalidPlatformFee {});
+    }
+    let fee_recipient = deps.api.addr_validate(fee_recipient.as_str())?;
+    
+    CONFIG.update(deps.storage, |mut exists| -> StdResult<_> {
+        exists.platform_fee = platform_fee;
+        exists.fee_recipient = fee_recipient;
+        Ok(exists)
+    })?;
+
+    Ok(Response::new().add_attribute("action", "update_platform_fee"))
+}
 const INSTANTIATE_TOKEN_REPLY_ID: u64 = 2;
 
 pub fn execute_add_collection(
     deps: DepsMut,
     info: MessageInfo,
-    msg: CollectionInstantiateMsg
+    mut msg: CollectionInstantiateMsg
 ) -> Result<Response, ContractError> {
 
     // check_owner(&deps, &info)?;
     
     let cfg = CONFIG.load(deps.storage)?;
+
+    //The platform fee always comes from the marketplace config
+    msg.platform_fee = None;
+    if cfg.platform_fee > 0 {
+        msg.platform_fee = Some(PlatformFee {
+            address: cfg.fee_recipient.clone(),
+            rate: cfg.platform_fee
+        });
+    }
+
     let record = CollectionRecord {
         owner: info.sender.clone(),
         uri: msg.uri.clone(),
@@ -275,6 +364,8 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} 
             => to_binary(&query_config(deps)?),
+        QueryMsg::PendingOwner {} 
+            => to_binary(&PendingOwnerResponse { pending_owner: PENDING_OWNER.may_load(deps.storage)? }),
         QueryMsg::Collection {id} 
             => to_binary(&query_collection(deps, id)?),
         QueryMsg::ListCollections {start_after, limit} 
@@ -291,7 +382,9 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         owner: cfg.owner,
         max_collection_id: cfg.max_collection_id,
         collection_code_id: cfg.collection_code_id,
-        cw721_base_code_id: cfg.cw721_base_code_id
+        cw721_base_code_id: cfg.cw721_base_code_id,
+        platform_fee: cfg.platform_fee,
+        fee_recipient: cfg.fee_recipient
     })
     
 }
@@ -376,6 +469,21 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, C
             previous_contract: version.contract,
         });
     }
+
+    //Marketplaces deployed before the platform fee start with none, the owner as its recipient
+    if CONFIG.load(deps.storage).is_err() {
+        let legacy = LEGACY_CONFIG.load(deps.storage)?;
+        CONFIG.save(deps.storage, &Config {
+            owner: legacy.owner.clone(),
+            max_collection_id: legacy.max_collection_id,
+            collection_code_id: legacy.collection_code_id,
+            cw721_base_code_id: legacy.cw721_base_code_id,
+            platform_fee: 0,
+            fee_recipient: legacy.owner,
+        })?;
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     Ok(Response::default())
 }
 
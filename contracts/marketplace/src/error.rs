@@ -16,6 +16,9 @@ pub enum ContractError {
 
     #[error("InvalidTokenReplyId")]
     InvalidTokenReplyId {},
+
+    #[error("InvalidPlatformFee")]
+    InvalidPlatformFee {},
     
     #[error("Unauthorized")]
     Unauthorized {},
@@ -77,4 +80,10 @@ pub enum ContractError {
 
     #[error("Count {count}")]
     Count { count: u64 },
+
+    #[error("NoPendingOwner")]
+    NoPendingOwner {},
+
+    #[error("OwnershipTransferExpired")]
+    OwnershipTransferExpired {},
 }
@@ -2,6 +2,7 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use cw20::{Cw20ReceiveMsg};
 use cosmwasm_std::{Uint128, Addr};
+use cw_utils::Expiration;
 
 use marble_collection::msg::{InstantiateMsg as CollectionInstantiateMsg, ExecuteMsg as CollectionExecuteMsg};
 
@@ -9,18 +10,29 @@ use marble_collection::msg::{InstantiateMsg as CollectionInstantiateMsg, Execute
 pub struct InstantiateMsg {
     pub collection_code_id: u64,
     pub cw721_base_code_id: u64,
+    // in millionths of every sale in the collections added here
+    pub platform_fee: u32,
+    pub fee_recipient: Addr,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
+    // Proposes the new owner, who has to send AcceptOwnership
     UpdateConfig {
-        new_owner: Addr
+        new_owner: Addr,
+        expires: Option<Expiration>
     },
+    AcceptOwnership {},
+    CancelOwnershipTransfer {},
     UpdateConstants {
         collection_code_id: u64,
         cw721_base_code_id: u64,
     },
+    UpdatePlatformFee {
+        platform_fee: u32,
+        fee_recipient: Addr
+    },
     // AddCollection {
     //     collection_addr: Addr,
     //     cw721_addr: Addr
@@ -43,6 +55,7 @@ pub enum ExecuteMsg {
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    PendingOwner {},
     Collection {
         id: u32
     },
@@ -55,14 +68,27 @@ pub enum QueryMsg {
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PendingOwner {
+    pub address: Addr,
+    // AcceptOwnership fails once expired
+    pub expires: Option<Expiration>
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PendingOwnerResponse {
+    pub pending_owner: Option<PendingOwner>
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
 pub struct ConfigResponse {
     pub owner: Addr,
     pub max_collection_id: u32,
     pub collection_code_id: u64,
-    pub cw721_base_code_id: u64
-
+    pub cw721_base_code_id: u64,
+    pub platform_fee: u32,
+    pub fee_recipient: Addr
 }
 
 
@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::{Addr, Uint128};
 use cw_storage_plus::{Item, Map};
-use crate::msg::CollectionRecord;
+use crate::msg::{CollectionRecord, PendingOwner};
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
     /// Owner If None set, contract is frozen.
@@ -11,10 +11,27 @@ pub struct Config {
     pub max_collection_id: u32,
     pub collection_code_id: u64,
     pub cw721_base_code_id: u64,
+    pub platform_fee: u32,
+    pub fee_recipient: Addr,
 }
 
 pub const CONFIG_KEY: &str = "config";
 pub const CONFIG: Item<Config> = Item::new(CONFIG_KEY);
 
+// Config stored before the platform fee, only read by migrate
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyConfig {
+    pub owner: Addr,
+    pub max_collection_id: u32,
+    pub collection_code_id: u64,
+    pub cw721_base_code_id: u64,
+}
+
+pub const LEGACY_CONFIG: Item<LegacyConfig> = Item::new(CONFIG_KEY);
+
+// Owner proposed by the current owner, takes over once it sends AcceptOwnership
+pub const PENDING_OWNER_KEY: &str = "pending_owner";
+pub const PENDING_OWNER: Item<PendingOwner> = Item::new(PENDING_OWNER_KEY);
+
 pub const COLLECTIONS_KEY: &str = "collections";
 pub const COLLECTIONS: Map<u32, CollectionRecord> = Map::new(COLLECTIONS_KEY);
@@ -2,7 +2,7 @@ use std::ops::Index;
 
 #[cfg(not(feature = "library"))]
 use crate::ContractError;
-use crate::state::{Config, CONFIG, TOKENS};
+use crate::state::{Config, CONFIG, PENDING_OWNER, TOKENS};
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
     to_binary, Addr, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Reply, ReplyOn, Response, Api,
@@ -19,7 +19,7 @@ use cw2::{get_contract_version};
 use cw721_base::{
     msg::ExecuteMsg as Cw721ExecuteMsg, Extension
 };
-use crate::msg::{ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg, MigrateMsg, };
+use crate::msg::{ConfigResponse, PendingOwnerResponse, ExecuteMsg, InstantiateMsg, QueryMsg, MigrateMsg, };
 
 use cw20::{ Balance};
 
@@ -60,6 +60,9 @@ pub fn instantiate(
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetConfig {} => to_binary(&query_config(deps)?),
+        QueryMsg::GetPendingOwner {} => to_binary(&PendingOwnerResponse {
+            pending_owner: PENDING_OWNER.may_load(deps.storage)?
+        }),
         QueryMsg::GetToken {index} => to_binary(&query_get_token(deps, index)?),
     }
 }
@@ -93,7 +96,9 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, crate::ContractError> {
     match msg {
-        ExecuteMsg::UpdateOwner { owner } => util::execute_update_owner(deps.storage, info.sender, owner),
+        ExecuteMsg::UpdateOwner { owner, expires } => util::execute_update_owner(deps.storage, deps.api, info.sender, owner, expires),
+        ExecuteMsg::AcceptOwnership {} => util::execute_accept_ownership(deps.storage, &env.block, info.sender),
+        ExecuteMsg::CancelOwnershipTransfer {} => util::execute_cancel_ownership_transfer(deps.storage, info.sender),
         ExecuteMsg::UpdateEnabled { enabled } => util::execute_update_enabled(deps.storage, info.sender, enabled),
         ExecuteMsg::UpdatePrice { price } => util::execute_update_price(deps.storage, info.sender, price),
         ExecuteMsg::SetToken {token_id} => execute_set_token(deps, token_id),
@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 use cosmwasm_std::{Addr, Uint128};
 use cw_storage_plus::Item;
 use cw_storage_plus::{Map};
+use crate::msg::PendingOwner;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
@@ -19,6 +20,10 @@ pub struct Config {
 pub const CONFIG_KEY: &str = "config";
 pub const CONFIG: Item<Config> = Item::new(CONFIG_KEY);
 
+// Owner proposed by the current owner, takes over once it sends AcceptOwnership
+pub const PENDING_OWNER_KEY: &str = "pending_owner";
+pub const PENDING_OWNER: Item<PendingOwner> = Item::new(PENDING_OWNER_KEY);
+
 pub const TOKENS_KEY: &str = "tokens";
 pub const TOKENS: Map<u32, String> = Map::new(TOKENS_KEY);
 
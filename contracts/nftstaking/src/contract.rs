@@ -1,6 +1,6 @@
 #[cfg(not(feature = "library"))]
 use crate::ContractError;
-use crate::state::{Config, CONFIG, STAKING};
+use crate::state::{Config, CONFIG, PENDING_OWNER, STAKING};
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
     to_binary, Addr, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Reply, ReplyOn, Response, Api,
@@ -20,7 +20,7 @@ use cw721_base::{
     msg::ExecuteMsg as Cw721ExecuteMsg, msg::InstantiateMsg as Cw721InstantiateMsg, Extension, 
     msg::MintMsg, msg::BatchMintMsg, msg::QueryMsg as Cw721QueryMsg,  msg::EditMsg
 };
-use crate::msg::{ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg, MigrateMsg, NftReceiveMsg, StakingInfo};
+use crate::msg::{ConfigResponse, PendingOwnerResponse, ExecuteMsg, InstantiateMsg, QueryMsg, MigrateMsg, NftReceiveMsg, StakingInfo};
 use cw_utils::{Expiration, Scheduled};
 use cw20::{Cw20ReceiveMsg, Cw20ExecuteMsg, Cw20CoinVerified, Balance};
 use cw_utils::parse_reply_instantiate_data;
@@ -69,7 +69,9 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, crate::ContractError> {
     match msg {
-        ExecuteMsg::UpdateOwner { owner } => util::execute_update_owner(deps.storage, info.sender, owner),
+        ExecuteMsg::UpdateOwner { owner, expires } => util::execute_update_owner(deps.storage, deps.api, info.sender, owner, expires),
+        ExecuteMsg::AcceptOwnership {} => util::execute_accept_ownership(deps.storage, &env.block, info.sender),
+        ExecuteMsg::CancelOwnershipTransfer {} => util::execute_cancel_ownership_transfer(deps.storage, info.sender),
         ExecuteMsg::UpdateEnabled { enabled } => util::execute_update_enabled(deps.storage, info.sender, enabled),
         ExecuteMsg::UpdateConfig { cw20_address, daily_reward, interval, lock_time } => execute_update_config(deps.storage, info.sender, cw20_address, daily_reward, interval, lock_time),
         ExecuteMsg::ReceiveNft(msg) => execute_receive_nft(deps, env, info, msg),
@@ -332,6 +334,9 @@ pub fn execute_withdraw_id(
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetConfig {} => to_binary(&query_config(deps)?),
+        QueryMsg::GetPendingOwner {} => to_binary(&PendingOwnerResponse {
+            pending_owner: PENDING_OWNER.may_load(deps.storage)?
+        }),
         QueryMsg::GetStaking { address} => to_binary(&query_get_staking(deps, env, address)?),
     }
 }
@@ -145,4 +145,10 @@ pub enum ContractError {
 
     #[error("InsufficientRoyalty")]
     InsufficientRoyalty {},
+
+    #[error("NoPendingOwner")]
+    NoPendingOwner {},
+
+    #[error("OwnershipTransferExpired")]
+    OwnershipTransferExpired {},
 }
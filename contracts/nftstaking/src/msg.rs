@@ -1,12 +1,9 @@
 use cosmwasm_std::{Addr, Uint128};
-use cw20::Cw20ReceiveMsg;
 use cw721::Cw721ReceiveMsg;
 
-use cw721_base::Extension;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use cw_utils::{Expiration, Scheduled};
-use cw20::Denom;
+use cw_utils::Expiration;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
@@ -20,9 +17,13 @@ pub struct InstantiateMsg {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
+    // Proposes the new owner, who has to send AcceptOwnership
     UpdateOwner {
         owner: Addr,
+        expires: Option<Expiration>
     },
+    AcceptOwnership {},
+    CancelOwnershipTransfer {},
     UpdateEnabled {
         enabled: bool
     },
@@ -55,6 +56,7 @@ pub enum NftReceiveMsg {
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     GetConfig {},
+    GetPendingOwner {},
     GetStaking {
         address: Addr
     }
@@ -90,4 +92,16 @@ pub struct StakingListResponse {
 
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct MigrateMsg {}
\ No newline at end of file
+pub struct MigrateMsg {}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingOwner {
+    pub address: Addr,
+    // AcceptOwnership fails once expired
+    pub expires: Option<Expiration>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingOwnerResponse {
+    pub pending_owner: Option<PendingOwner>
+}
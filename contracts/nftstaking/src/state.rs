@@ -6,7 +6,7 @@ use cosmwasm_std::{Addr, Uint128};
 use cw_storage_plus::Item;
 use cw_utils::{Expiration, Scheduled};
 use cw_storage_plus::{Map};
-use crate::msg::{StakingInfo};
+use crate::msg::{StakingInfo, PendingOwner};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
@@ -22,6 +22,10 @@ pub struct Config {
 pub const CONFIG_KEY: &str = "config";
 pub const CONFIG: Item<Config> = Item::new(CONFIG_KEY);
 
+// Owner proposed by the current owner, takes over once it sends AcceptOwnership
+pub const PENDING_OWNER_KEY: &str = "pending_owner";
+pub const PENDING_OWNER: Item<PendingOwner> = Item::new(PENDING_OWNER_KEY);
+
 pub const STAKING_KEY: &str = "staking";
 pub const STAKING: Map<Addr, StakingInfo> = Map::new(STAKING_KEY);
 
@@ -1,10 +1,12 @@
 use cosmwasm_std::{
     to_binary,  Response, StdResult, Uint128, Coin, BankMsg,
-    WasmMsg, WasmQuery, QueryRequest, Addr, Storage, CosmosMsg,  QuerierWrapper, BalanceResponse as NativeBalanceResponse, BankQuery
+    WasmMsg, WasmQuery, QueryRequest, Addr, Storage, CosmosMsg, BlockInfo, Api,  QuerierWrapper, BalanceResponse as NativeBalanceResponse, BankQuery
 };
 use cw20::{Balance, Cw20ExecuteMsg, Denom, BalanceResponse as CW20BalanceResponse, Cw20QueryMsg};
 use crate::error::ContractError;
-use crate::state::CONFIG;
+use crate::state::{CONFIG, PENDING_OWNER};
+use crate::msg::PendingOwner;
+use cw_utils::Expiration;
 
 pub const MAX_LIMIT: u32 = 30;
 pub const DEFAULT_LIMIT: u32 = 10;
@@ -37,18 +39,61 @@ pub fn check_owner(
 
 pub fn execute_update_owner(
     storage: &mut dyn Storage,
+    api: &dyn Api,
     address: Addr,
     owner: Addr,
+    expires: Option<Expiration>
 ) -> Result<Response, ContractError> {
     // authorize owner
     check_owner(storage, address)?;
-    
+    // a mistyped owner could never accept
+    let owner = api.addr_validate(owner.as_str())?;
+
+    PENDING_OWNER.save(storage, &PendingOwner {
+        address: owner.clone(),
+        expires
+    })?;
+
+    Ok(Response::new().add_attribute("action", "propose_owner").add_attribute("pending_owner", owner))
+}
+
+pub fn execute_accept_ownership(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    address: Addr
+) -> Result<Response, ContractError> {
+    let pending = PENDING_OWNER.may_load(storage)?.ok_or(ContractError::NoPendingOwner {})?;
+    if pending.address != address {
+        return Err(ContractError::Unauthorized {});
+    }
+    if let Some(expires) = pending.expires {
+        if expires.is_expired(block) {
+            return Err(ContractError::OwnershipTransferExpired {});
+        }
+    }
+
     CONFIG.update(storage, |mut exists| -> StdResult<_> {
-        exists.owner = owner.clone();
+        exists.owner = address.clone();
         Ok(exists)
     })?;
+    PENDING_OWNER.remove(storage);
+
+    Ok(Response::new().add_attribute("action", "accept_owner").add_attribute("owner", address.clone()))
+}
+
+pub fn execute_cancel_ownership_transfer(
+    storage: &mut dyn Storage,
+    address: Addr
+) -> Result<Response, ContractError> {
+    // authorize owner
+    check_owner(storage, address)?;
+
+    if PENDING_OWNER.may_load(storage)?.is_none() {
+        return Err(ContractError::NoPendingOwner {});
+    }
+    PENDING_OWNER.remove(storage);
 
-    Ok(Response::new().add_attribute("action", "update_config").add_attribute("owner", owner.clone()))
+    Ok(Response::new().add_attribute("action", "cancel_owner"))
 }
 
 pub fn execute_update_enabled (